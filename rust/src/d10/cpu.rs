@@ -0,0 +1,221 @@
+use std::fmt;
+
+use nom::{
+    bytes::complete::tag,
+    character::complete::{line_ending, multispace0, space1},
+    combinator::{all_consuming, map},
+    multi::separated_list0,
+    sequence::{preceded, terminated},
+    IResult,
+};
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Instruction {
+    Noop,
+    Addx(i32),
+}
+
+struct InstructionSpec {
+    mnemonic: &'static str,
+    cycles: usize,
+    // parses whatever follows the mnemonic
+    operands: fn(&str) -> IResult<&str, Instruction>,
+}
+
+// adding an opcode means a variant above, a row here and an arm in `Instruction::execute`
+const INSTRUCTION_SET: &[InstructionSpec] = &[
+    InstructionSpec {
+        mnemonic: "noop",
+        cycles: 1,
+        operands: |input| Ok((input, Instruction::Noop)),
+    },
+    InstructionSpec {
+        mnemonic: "addx",
+        cycles: 2,
+        operands: |input| {
+            map(
+                preceded(space1, nom::character::complete::i32),
+                Instruction::Addx,
+            )(input)
+        },
+    },
+];
+
+impl Instruction {
+    pub fn parse(input: &str) -> IResult<&str, Self> {
+        let mut last_error = None;
+        for spec in INSTRUCTION_SET {
+            match preceded(tag(spec.mnemonic), spec.operands)(input) {
+                Ok(result) => return Ok(result),
+                Err(e) => last_error = Some(e),
+            }
+        }
+        Err(last_error.unwrap())
+    }
+
+    pub fn mnemonic(&self) -> &'static str {
+        match self {
+            Instruction::Noop => "noop",
+            Instruction::Addx(_) => "addx",
+        }
+    }
+
+    pub fn cycles(&self) -> usize {
+        INSTRUCTION_SET
+            .iter()
+            .find(|spec| spec.mnemonic == self.mnemonic())
+            .map(|spec| spec.cycles)
+            .unwrap()
+    }
+
+    // runs at the end of the instruction's last cycle
+    fn execute(&self, registers: &mut Registers) {
+        match self {
+            Instruction::Noop => {}
+            Instruction::Addx(value) => registers.x += value,
+        }
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Instruction::Noop => write!(f, "{}", self.mnemonic()),
+            Instruction::Addx(value) => write!(f, "{} {}", self.mnemonic(), value),
+        }
+    }
+}
+
+pub fn parse_program(input: &str) -> Result<Vec<Instruction>, nom::Err<nom::error::Error<&str>>> {
+    let (_, program) = all_consuming(terminated(
+        separated_list0(line_ending, Instruction::parse),
+        multispace0,
+    ))(input)?;
+    Ok(program)
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Registers {
+    pub x: i32,
+}
+
+impl Default for Registers {
+    fn default() -> Self {
+        Registers { x: 1 }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Tick {
+    // 1-based, like the puzzle text
+    pub cycle: usize,
+    pub instruction: Instruction,
+    pub during: Registers,
+    pub after: Registers,
+}
+
+#[derive(Debug, Clone)]
+pub struct Cpu {
+    program: Vec<Instruction>,
+    registers: Registers,
+    cycle: usize,
+    pc: usize,
+    // cycles the current instruction still needs, 0 when nothing is in flight
+    remaining: usize,
+}
+
+impl Cpu {
+    pub fn new(program: Vec<Instruction>) -> Self {
+        Cpu {
+            program,
+            registers: Registers::default(),
+            cycle: 0,
+            pc: 0,
+            remaining: 0,
+        }
+    }
+
+    pub fn registers(&self) -> Registers {
+        self.registers
+    }
+}
+
+impl Iterator for Cpu {
+    type Item = Tick;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let instruction = *self.program.get(self.pc)?;
+        if self.remaining == 0 {
+            self.remaining = instruction.cycles();
+        }
+        self.cycle += 1;
+        let during = self.registers;
+        self.remaining -= 1;
+        if self.remaining == 0 {
+            instruction.execute(&mut self.registers);
+            self.pc += 1;
+        }
+        Some(Tick {
+            cycle: self.cycle,
+            instruction,
+            during,
+            after: self.registers,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_program() {
+        let input = r#"noop
+addx 3
+addx -5
+"#;
+        let expected = vec![
+            Instruction::Noop,
+            Instruction::Addx(3),
+            Instruction::Addx(-5),
+        ];
+        assert_eq!(parse_program(input).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_parse_program_rejects_unknown_opcode() {
+        assert!(parse_program("noop\nmulx 3").is_err());
+        assert!(parse_program("addx three").is_err());
+    }
+
+    #[test]
+    fn test_cycles() {
+        assert_eq!(Instruction::Noop.cycles(), 1);
+        assert_eq!(Instruction::Addx(7).cycles(), 2);
+    }
+
+    #[test]
+    fn test_ticks() {
+        let program = parse_program("noop\naddx 3\naddx -5").unwrap();
+        let ticks: Vec<Tick> = Cpu::new(program).collect();
+        let during: Vec<i32> = ticks.iter().map(|tick| tick.during.x).collect();
+        let after: Vec<i32> = ticks.iter().map(|tick| tick.after.x).collect();
+        assert_eq!(during, vec![1, 1, 1, 4, 4]);
+        assert_eq!(after, vec![1, 1, 4, 4, -1]);
+        assert_eq!(ticks[2].cycle, 3);
+        assert_eq!(ticks[2].instruction, Instruction::Addx(3));
+    }
+
+    #[test]
+    fn test_cpu_state() {
+        let program = parse_program("addx 3\nnoop").unwrap();
+        let mut cpu = Cpu::new(program);
+        assert_eq!(cpu.registers().x, 1);
+        cpu.next();
+        assert_eq!(cpu.registers().x, 1);
+        cpu.next();
+        assert_eq!(cpu.registers().x, 4);
+        assert_eq!(cpu.next().map(|tick| tick.cycle), Some(3));
+        assert_eq!(cpu.next(), None);
+    }
+}
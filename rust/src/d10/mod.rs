@@ -1,50 +1,155 @@
-use std::collections::HashSet;
+use std::{error::Error, fmt};
 
+mod cpu;
+mod ocr;
+mod trace;
+
+use cpu::{parse_program, Cpu};
+pub use trace::{Trace, TraceOptions};
+
+// X at the start and then after every cycle, so index k is the value during cycle k + 1
 fn program(input: &str) -> Vec<i32> {
-    let mut x: Vec<i32> = vec![1];
-
-    for (i, line) in input.split("\n").collect::<Vec<&str>>().iter().enumerate() {
-        let prev = x.last().unwrap().to_owned();
-        if &line[0..4] == "addx" {
-            x.push(prev);
-            let arg: i32 = line[5..].parse::<i32>().unwrap();
-            x.push(prev + arg);
-        }
+    let cpu = Cpu::new(parse_program(input).unwrap());
+    std::iter::once(cpu.registers().x)
+        .chain(cpu.map(|tick| tick.after.x))
+        .collect()
+}
 
-        if &line[0..4] == "noop" {
-            x.push(prev);
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct CrtConfig {
+    pub width: usize,
+    pub height: usize,
+    // the sprite is centred on X, an even width puts the extra pixel on the right
+    pub sprite_width: usize,
+    // signal strength is sampled during sample_start, sample_start + sample_stride, ...
+    // for as long as the beam is on the screen
+    pub sample_start: usize,
+    pub sample_stride: usize,
+}
+
+impl Default for CrtConfig {
+    fn default() -> Self {
+        CrtConfig {
+            width: 40,
+            height: 6,
+            sprite_width: 3,
+            sample_start: 20,
+            sample_stride: 40,
         }
     }
-    x
 }
 
-fn draw_program(program: &Vec<i32>) {
-    for j in 0..6 {
-        let mut row: String = String::from("");
-        for i in 0..40 {
-            if program[i + j*40] >= i as i32 - 1
-            && program[i + j*40] <= i as i32 + 1 {
-                row.push_str("@");
-            } else {
-                row.push_str(" ");
+#[derive(Debug, PartialEq)]
+pub enum CrtConfigError {
+    EmptyRows,
+    SampleBeforeFirstCycle,
+    ZeroSampleStride,
+}
+
+impl fmt::Display for CrtConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CrtConfigError::EmptyRows => write!(f, "the screen has to be at least one pixel wide"),
+            CrtConfigError::SampleBeforeFirstCycle => {
+                write!(f, "sampling can't start before cycle 1")
+            }
+            CrtConfigError::ZeroSampleStride => {
+                write!(f, "samples have to be at least one cycle apart")
             }
         }
-        println!("{}", row);
     }
 }
 
-pub fn total_signal_strength(input: &str) -> i32 {
-    let x = program(input);
-    let cycles_of_interest: HashSet<usize> = HashSet::from([20, 60, 100, 140, 180, 220]);
-    let mut sum = 0;
-    for k in cycles_of_interest {
-        sum += x[k-1] * k as i32;
+impl Error for CrtConfigError {}
+
+impl CrtConfig {
+    // a zero height or sprite width is fine, it just leaves the screen dark
+    pub fn validate(&self) -> Result<(), CrtConfigError> {
+        if self.width == 0 {
+            return Err(CrtConfigError::EmptyRows);
+        }
+        if self.sample_start == 0 {
+            return Err(CrtConfigError::SampleBeforeFirstCycle);
+        }
+        if self.sample_stride == 0 {
+            return Err(CrtConfigError::ZeroSampleStride);
+        }
+        Ok(())
     }
-    sum
+
+    // column of the pixel drawn during the given 1-based cycle
+    fn beam_position(&self, cycle: usize) -> usize {
+        (cycle - 1) % self.width
+    }
+
+    fn sprite_covers(&self, x: i32, column: usize) -> bool {
+        let left = x - (self.sprite_width as i32 - 1) / 2;
+        (left..left + self.sprite_width as i32).contains(&(column as i32))
+    }
+
+    fn sample_cycles(&self) -> impl Iterator<Item = usize> {
+        (self.sample_start..=self.width * self.height).step_by(self.sample_stride)
+    }
+}
+
+// one row per CRT line, true where the pixel is lit
+type Screen = Vec<Vec<bool>>;
+
+fn draw_program(program: &[i32], config: &CrtConfig) -> Screen {
+    (0..config.height)
+        .map(|j| {
+            (0..config.width)
+                .map(|i| {
+                    // pixels past the end of the program stay dark
+                    program
+                        .get(i + j * config.width)
+                        .is_some_and(|x| config.sprite_covers(*x, i))
+                })
+                .collect()
+        })
+        .collect()
+}
+
+fn screen_to_string(screen: &Screen) -> String {
+    screen
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|lit| if *lit { '#' } else { '.' })
+                .collect::<String>()
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+pub fn signal_strength(input: &str, config: &CrtConfig) -> Result<i32, CrtConfigError> {
+    config.validate()?;
+    let x = program(input);
+    Ok(config
+        .sample_cycles()
+        .filter_map(|k| x.get(k - 1).map(|x| x * k as i32))
+        .sum())
+}
+
+pub fn total_signal_strength(input: &str) -> i32 {
+    signal_strength(input, &CrtConfig::default()).unwrap()
+}
+
+pub fn draw_crt_with(input: &str, config: &CrtConfig) -> Result<String, CrtConfigError> {
+    config.validate()?;
+    Ok(screen_to_string(&draw_program(&program(input), config)))
+}
+
+pub fn draw_crt(input: &str) -> String {
+    draw_crt_with(input, &CrtConfig::default()).unwrap()
+}
+
+pub fn read_crt(input: &str) -> String {
+    ocr::read_letters(&draw_program(&program(input), &CrtConfig::default()))
 }
 
-pub fn draw_crt(input: &str) {
-    draw_program(&program(input));
+pub fn trace_program(input: &str, options: &TraceOptions) -> Result<Trace, CrtConfigError> {
+    trace::trace(parse_program(input).unwrap(), options)
 }
 
 #[cfg(test)]
@@ -537,7 +642,97 @@ noop
 noop
 noop"#;
 
-        draw_program(&program(input));
-        assert!(false);
+        let expected = r#"##..##..##..##..##..##..##..##..##..##..
+###...###...###...###...###...###...###.
+####....####....####....####....####....
+#####.....#####.....#####.....#####.....
+######......######......######......####
+#######.......#######.......#######....."#;
+        assert_eq!(
+            screen_to_string(&draw_program(&program(input), &CrtConfig::default())),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_default_config_sample_cycles() {
+        let cycles: Vec<usize> = CrtConfig::default().sample_cycles().collect();
+        assert_eq!(cycles, vec![20, 60, 100, 140, 180, 220]);
+    }
+
+    #[test]
+    fn test_sprite_covers() {
+        let config = CrtConfig {
+            sprite_width: 4,
+            ..Default::default()
+        };
+        let covered: Vec<usize> = (0..10).filter(|i| config.sprite_covers(5, *i)).collect();
+        assert_eq!(covered, vec![4, 5, 6, 7]);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_custom_geometry() {
+        // X runs 1, 1, 1, 4, 4, -1, -1, ...
+        let input = r#"noop
+addx 3
+addx -5
+noop
+noop
+noop
+noop
+noop"#;
+        let config = CrtConfig {
+            width: 5,
+            height: 2,
+            sprite_width: 1,
+            sample_start: 2,
+            sample_stride: 3,
+        };
+        let expected = r#".#..#
+....."#;
+        assert_eq!(draw_crt_with(input, &config), Ok(expected.to_string()));
+        // cycles 2, 5 and 8: 2 * 1 + 5 * 4 + 8 * -1
+        assert_eq!(signal_strength(input, &config), Ok(14));
+    }
+
+    #[test]
+    fn test_invalid_config() {
+        for (config, expected) in [
+            (
+                CrtConfig {
+                    width: 0,
+                    ..Default::default()
+                },
+                CrtConfigError::EmptyRows,
+            ),
+            (
+                CrtConfig {
+                    sample_start: 0,
+                    ..Default::default()
+                },
+                CrtConfigError::SampleBeforeFirstCycle,
+            ),
+            (
+                CrtConfig {
+                    sample_stride: 0,
+                    ..Default::default()
+                },
+                CrtConfigError::ZeroSampleStride,
+            ),
+        ] {
+            assert_eq!(config.validate(), Err(expected));
+            assert!(draw_crt_with("noop", &config).is_err());
+            assert!(signal_strength("noop", &config).is_err());
+        }
+    }
+
+    #[test]
+    fn test_empty_screen_is_valid() {
+        let config = CrtConfig {
+            height: 0,
+            sprite_width: 0,
+            ..Default::default()
+        };
+        assert_eq!(draw_crt_with("noop", &config), Ok(String::new()));
+    }
+}
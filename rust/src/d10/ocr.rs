@@ -0,0 +1,92 @@
+use super::Screen;
+
+const GLYPH_WIDTH: usize = 4;
+const GLYPH_HEIGHT: usize = 6;
+// glyphs sit in 5 column cells, the last column being the gap between letters
+const CELL_WIDTH: usize = GLYPH_WIDTH + 1;
+
+// the letters AoC is known to draw, rows joined top to bottom
+const FONT: &[(char, &str)] = &[
+    ('A', ".##.#..##..######..##..#"),
+    ('B', "###.#..####.#..##..####."),
+    ('C', ".##.#..##...#...#..#.##."),
+    ('E', "#####...###.#...#...####"),
+    ('F', "#####...###.#...#...#..."),
+    ('G', ".##.#..##...#.###..#.###"),
+    ('H', "#..##..######..##..##..#"),
+    ('I', ".###..#...#...#...#..###"),
+    ('J', "..##...#...#...##..#.##."),
+    ('K', "#..##.#.##..#.#.#.#.#..#"),
+    ('L', "#...#...#...#...#...####"),
+    ('O', ".##.#..##..##..##..#.##."),
+    ('P', "###.#..##..####.#...#..."),
+    ('R', "###.#..##..####.#.#.#..#"),
+    ('S', ".####...#....##....####."),
+    ('U', "#..##..##..##..##..#.##."),
+    ('Y', "#...#....#.#..#...#...#."),
+    ('Z', "####...#..#..#..#...####"),
+];
+
+// unknown glyphs come back as '?' so a bad render is still visible in the answer
+pub fn read_letters(screen: &Screen) -> String {
+    if screen.len() != GLYPH_HEIGHT {
+        return String::new();
+    }
+    let width = screen[0].len();
+    // the last letter doesn't need its gap column
+    (0..(width + 1) / CELL_WIDTH)
+        .map(|letter| {
+            let glyph: String = screen
+                .iter()
+                .flat_map(|row| {
+                    row.iter()
+                        .skip(letter * CELL_WIDTH)
+                        .take(GLYPH_WIDTH)
+                        .map(|lit| if *lit { '#' } else { '.' })
+                })
+                .collect();
+            FONT.iter()
+                .find(|(_, pattern)| *pattern == glyph)
+                .map(|(c, _)| *c)
+                .unwrap_or('?')
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn screen_from(drawing: &str) -> Screen {
+        drawing
+            .lines()
+            .map(|line| line.chars().map(|c| c == '#').collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_read_letters() {
+        let screen = screen_from(
+            r#"###..####..##..###..#..#.###..####.###..
+#..#....#.#..#.#..#.#.#..#..#.#....#..#.
+#..#...#..#....#..#.##...#..#.###..###..
+###...#...#.##.###..#.#..###..#....#..#.
+#....#....#..#.#....#.#..#....#....#..#.
+#....####..###.#....#..#.#....####.###.."#,
+        );
+        assert_eq!(read_letters(&screen), "PZGPKPEB");
+    }
+
+    #[test]
+    fn test_read_letters_unknown_glyph() {
+        let screen = screen_from(
+            r#".##..####.
+#..#.#..#.
+#..#.#..#.
+####.#..#.
+#..#.#..#.
+#..#.####."#,
+        );
+        assert_eq!(read_letters(&screen), "A?");
+    }
+}
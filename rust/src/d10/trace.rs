@@ -0,0 +1,205 @@
+use std::{collections::HashSet, fmt, ops::RangeInclusive};
+
+use super::{
+    cpu::{Cpu, Instruction, Tick},
+    CrtConfig, CrtConfigError,
+};
+
+#[derive(Debug, Default, Clone)]
+pub struct TraceOptions {
+    // only these cycles end up in the table, everything is still executed
+    pub cycles: Option<RangeInclusive<usize>>,
+    pub break_on_cycles: HashSet<usize>,
+    // matched against X during the cycle
+    pub break_on_x: HashSet<i32>,
+    // only used for the beam position column
+    pub crt: CrtConfig,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Breakpoint {
+    Cycle(usize),
+    X { cycle: usize, x: i32 },
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Trace {
+    pub ticks: Vec<Tick>,
+    pub stopped_at: Option<Breakpoint>,
+    crt: CrtConfig,
+}
+
+impl TraceOptions {
+    fn breakpoint(&self, tick: &Tick) -> Option<Breakpoint> {
+        if self.break_on_cycles.contains(&tick.cycle) {
+            Some(Breakpoint::Cycle(tick.cycle))
+        } else if self.break_on_x.contains(&tick.during.x) {
+            Some(Breakpoint::X {
+                cycle: tick.cycle,
+                x: tick.during.x,
+            })
+        } else {
+            None
+        }
+    }
+}
+
+// runs the program until it finishes or a breakpoint is hit, the breaking cycle is included
+pub fn trace(program: Vec<Instruction>, options: &TraceOptions) -> Result<Trace, CrtConfigError> {
+    options.crt.validate()?;
+    let mut ticks = vec![];
+    for tick in Cpu::new(program) {
+        if options
+            .cycles
+            .as_ref()
+            .is_none_or(|cycles| cycles.contains(&tick.cycle))
+        {
+            ticks.push(tick);
+        }
+        if let Some(breakpoint) = options.breakpoint(&tick) {
+            return Ok(Trace {
+                ticks,
+                stopped_at: Some(breakpoint),
+                crt: options.crt,
+            });
+        }
+    }
+    Ok(Trace {
+        ticks,
+        stopped_at: None,
+        crt: options.crt,
+    })
+}
+
+impl fmt::Display for Breakpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Breakpoint::Cycle(cycle) => write!(f, "breakpoint at cycle {}", cycle),
+            Breakpoint::X { cycle, x } => write!(f, "breakpoint on X = {} at cycle {}", x, cycle),
+        }
+    }
+}
+
+impl fmt::Display for Trace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{:>5} | {:<11} | {:>8} | {:>7} | {:>4}",
+            "cycle", "instruction", "X during", "X after", "beam"
+        )?;
+        for tick in &self.ticks {
+            writeln!(
+                f,
+                "{:>5} | {:<11} | {:>8} | {:>7} | {:>4}",
+                tick.cycle,
+                tick.instruction.to_string(),
+                tick.during.x,
+                tick.after.x,
+                self.crt.beam_position(tick.cycle)
+            )?;
+        }
+        if let Some(breakpoint) = self.stopped_at {
+            writeln!(f, "stopped: {}", breakpoint)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::cpu::parse_program;
+    use super::*;
+
+    const INPUT: &str = r#"noop
+addx 3
+addx -5
+noop"#;
+
+    #[test]
+    fn test_trace_runs_to_completion() {
+        let trace = trace(parse_program(INPUT).unwrap(), &TraceOptions::default()).unwrap();
+        assert_eq!(trace.ticks.len(), 6);
+        assert_eq!(trace.stopped_at, None);
+    }
+
+    #[test]
+    fn test_break_on_cycle() {
+        let options = TraceOptions {
+            break_on_cycles: HashSet::from([3]),
+            ..Default::default()
+        };
+        let trace = trace(parse_program(INPUT).unwrap(), &options).unwrap();
+        assert_eq!(trace.ticks.len(), 3);
+        assert_eq!(trace.stopped_at, Some(Breakpoint::Cycle(3)));
+    }
+
+    #[test]
+    fn test_break_on_x() {
+        let options = TraceOptions {
+            break_on_x: HashSet::from([-1]),
+            ..Default::default()
+        };
+        let trace = trace(parse_program(INPUT).unwrap(), &options).unwrap();
+        assert_eq!(trace.stopped_at, Some(Breakpoint::X { cycle: 6, x: -1 }));
+    }
+
+    #[test]
+    fn test_cycle_range() {
+        let options = TraceOptions {
+            cycles: Some(2..=3),
+            ..Default::default()
+        };
+        let trace = trace(parse_program(INPUT).unwrap(), &options).unwrap();
+        let cycles: Vec<usize> = trace.ticks.iter().map(|tick| tick.cycle).collect();
+        assert_eq!(cycles, vec![2, 3]);
+    }
+
+    #[test]
+    fn test_display() {
+        let options = TraceOptions {
+            cycles: Some(3..=4),
+            break_on_cycles: HashSet::from([4]),
+            ..Default::default()
+        };
+        let trace = trace(parse_program(INPUT).unwrap(), &options).unwrap();
+        let expected = r#"cycle | instruction | X during | X after | beam
+    3 | addx 3      |        1 |       4 |    2
+    4 | addx -5     |        4 |       4 |    3
+stopped: breakpoint at cycle 4
+"#;
+        assert_eq!(trace.to_string(), expected);
+    }
+
+    #[test]
+    fn test_beam_position_wraps_at_crt_width() {
+        let options = TraceOptions {
+            crt: CrtConfig {
+                width: 4,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let trace = trace(parse_program(INPUT).unwrap(), &options).unwrap();
+        let beam: Vec<usize> = trace
+            .ticks
+            .iter()
+            .map(|tick| trace.crt.beam_position(tick.cycle))
+            .collect();
+        assert_eq!(beam, vec![0, 1, 2, 3, 0, 1]);
+    }
+
+    #[test]
+    fn test_invalid_crt_is_rejected() {
+        let options = TraceOptions {
+            crt: CrtConfig {
+                width: 0,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert_eq!(
+            trace(parse_program(INPUT).unwrap(), &options),
+            Err(CrtConfigError::EmptyRows)
+        );
+    }
+}
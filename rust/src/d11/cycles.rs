@@ -0,0 +1,92 @@
+use std::collections::HashMap;
+
+use super::{item_round, worry::WorryBackend, Monkeys, SimulationError};
+
+// items never interact, so each one can be followed on its own: at the start of a round an item
+// is fully described by the monkey holding it and its worry level, and once that pair comes
+// back around the rounds in between repeat forever
+pub fn run<B: WorryBackend>(
+    monkeys: &Monkeys<B::Level>,
+    num_rounds: u64,
+    worry_divisor: u64,
+    backend: &B,
+) -> Result<Vec<usize>, SimulationError> {
+    let mut num_inspected = vec![0; monkeys.len()];
+    for (holder, monkey) in monkeys.iter().enumerate() {
+        for item in &monkey.items {
+            let counts = item_inspections(
+                monkeys,
+                holder,
+                item.worry.clone(),
+                num_rounds,
+                worry_divisor,
+                backend,
+            )?;
+            for (total, count) in num_inspected.iter_mut().zip(counts) {
+                *total += count;
+            }
+        }
+    }
+    Ok(num_inspected)
+}
+
+fn item_inspections<B: WorryBackend>(
+    monkeys: &Monkeys<B::Level>,
+    holder: usize,
+    worry: B::Level,
+    num_rounds: u64,
+    worry_divisor: u64,
+    backend: &B,
+) -> Result<Vec<usize>, SimulationError> {
+    // cumulative[r] is how often each monkey inspected the item in the first r rounds
+    let mut cumulative = vec![vec![0; monkeys.len()]];
+    let mut seen = HashMap::new();
+    let mut state = (holder, worry);
+    for round_number in 1..=num_rounds {
+        if let Some(&cycle_start) = seen.get(&state) {
+            return Ok(extrapolate(&cumulative, cycle_start, num_rounds));
+        }
+        seen.insert(state.clone(), cumulative.len() - 1);
+
+        let mut counts = cumulative.last().unwrap().clone();
+        let (holder, worry) = state;
+        state = item_round(monkeys, holder, worry, worry_divisor, backend, &mut counts)
+            .map_err(|overflow| overflow.in_round(round_number))?;
+        cumulative.push(counts);
+    }
+    Ok(cumulative.pop().unwrap())
+}
+
+// counts after num_rounds given that the state at the start of round `cumulative.len() - 1`
+// is the same as the one at the start of round `cycle_start`
+fn extrapolate(cumulative: &[Vec<usize>], cycle_start: usize, num_rounds: u64) -> Vec<usize> {
+    let cycle_end = cumulative.len() - 1;
+    let cycle_length = (cycle_end - cycle_start) as u64;
+    let num_cycles = ((num_rounds - cycle_start as u64) / cycle_length) as usize;
+    let remainder = ((num_rounds - cycle_start as u64) % cycle_length) as usize;
+    (0..cumulative[0].len())
+        .map(|monkey| {
+            let per_cycle = cumulative[cycle_end][monkey] - cumulative[cycle_start][monkey];
+            let partial =
+                cumulative[cycle_start + remainder][monkey] - cumulative[cycle_start][monkey];
+            cumulative[cycle_start][monkey] + num_cycles * per_cycle + partial
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extrapolate() {
+        // one monkey, the cycle starts after round 1 and is 2 rounds long
+        let cumulative = vec![vec![0], vec![1], vec![3], vec![4]];
+        assert_eq!(extrapolate(&cumulative, 1, 3), vec![4]);
+        assert_eq!(extrapolate(&cumulative, 1, 4), vec![6]);
+        assert_eq!(
+            extrapolate(&cumulative, 1, 1_000_000_001),
+            vec![1_500_000_001]
+        );
+    }
+}
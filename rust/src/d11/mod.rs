@@ -1,10 +1,10 @@
 use nom::{
     branch::alt,
     bytes::complete::{tag, take_until},
-    character::complete::{digit1, line_ending, multispace0, space0},
-    combinator::{map, map_res},
-    multi::many0,
-    sequence::pair,
+    character::complete::{line_ending, multispace0, one_of, space0},
+    combinator::map,
+    multi::fold_many0,
+    sequence::{delimited, pair, preceded},
     IResult,
 };
 
@@ -15,7 +15,15 @@ use std::{
     rc::Rc,
 };
 
-const NUM_ROUNDS: usize = 20;
+mod cycles;
+mod parallel;
+mod trace;
+mod worry;
+
+pub use trace::SimulationTrace;
+pub use worry::WorryMode;
+use worry::{Exact, Modular, WorryBackend};
+
 trait Parseable {
     fn parse(input: &str) -> IResult<&str, Self>
     where
@@ -37,9 +45,9 @@ impl Parseable for Test {
     }
 }
 #[derive(Debug, PartialEq, Clone)]
-struct Monkey {
+struct Monkey<W = u64> {
     number: usize,
-    items: VecDeque<Item>,
+    items: VecDeque<Item<W>>,
     operation: Operation,
     test: Test,
     test_result_monkey: TestResultTargetMonkey,
@@ -51,7 +59,6 @@ impl Parseable for Monkey {
         Self: Sized,
     {
         let (input, _) = multispace0(input)?;
-        println!("{:?}", input);
         let (input, _) = tag("Monkey ")(input)?;
         let (input, monkey_number) = nom::character::complete::u64(input)?;
         let (input, _) = take_until("\n")(input)?;
@@ -61,25 +68,8 @@ impl Parseable for Monkey {
         let (input, items) = nom::multi::separated_list1(tag(", "), Item::parse)(input)?;
         let (input, _) = line_ending(input)?;
         let (input, _) = space0(input)?;
-        let (input, _) = tag("Operation: new = old ")(input)?;
-        let (input, operation) = alt((
-            // map(tag("* "), |_| -> Box<dyn Fn(u64) -> Operation> {
-            //     Box::new(|val| Operation::Multiply(val))
-            // }),
-            // map(tag("+ "), |_| -> Box<dyn Fn(u64) -> Operation> {
-            //     Box::new(|val| Operation::Add(val))
-            // }),
-            // map(tag("* old"), |_| -> Box<dyn Fn(u64) -> Operation> {
-            //     Box::new(|_| Operation::Square)
-            // }),
-            map(pair(tag("+ "), map_res(digit1, str::parse)), |(_, val)| {
-                Operation::Add(val)
-            }),
-            map(pair(tag("* "), map_res(digit1, str::parse)), |(_, val)| {
-                Operation::Multiply(val)
-            }),
-            map(tag("* old"), |_| Operation::Square),
-        ))(input)?;
+        let (input, _) = tag("Operation: new = ")(input)?;
+        let (input, operation) = Operation::parse(input)?;
         let (input, test) = Test::parse(input)?;
         let (input, test_result_monkey) = TestResultTargetMonkey::parse(input)?;
         Ok((
@@ -96,7 +86,7 @@ impl Parseable for Monkey {
     }
 }
 
-impl fmt::Display for Monkey {
+impl<W: fmt::Display> fmt::Display for Monkey<W> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
             f,
@@ -111,11 +101,30 @@ impl fmt::Display for Monkey {
     }
 }
 
-type Monkeys = Vec<Monkey>;
+impl Monkey {
+    fn with_backend<B: WorryBackend>(self, backend: &B) -> Monkey<B::Level> {
+        Monkey {
+            number: self.number,
+            items: self
+                .items
+                .into_iter()
+                .map(|item| Item {
+                    worry: backend.level(item.worry),
+                })
+                .collect(),
+            operation: self.operation,
+            test: self.test,
+            test_result_monkey: self.test_result_monkey,
+            num_inspected: self.num_inspected,
+        }
+    }
+}
+
+type Monkeys<W = u64> = Vec<Monkey<W>>;
 
 #[derive(Debug, PartialEq, Clone)]
-struct Item {
-    worry: u64,
+struct Item<W = u64> {
+    worry: W,
 }
 
 impl Parseable for Item {
@@ -125,13 +134,164 @@ impl Parseable for Item {
         Ok((input, Item { worry }))
     }
 }
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Operator {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+}
+
+// the right hand side of `new = ...`, evaluated once per inspected item
 #[derive(Debug, PartialEq, Clone)]
 enum Operation {
-    Add(u64),
-    Multiply(u64),
-    Square,
+    Old,
+    Literal(u64),
+    Binary(Box<Operation>, Operator, Box<Operation>),
+}
+
+impl Parseable for Operation {
+    // usual precedence: * and / bind tighter than + and -, everything is left associative
+    fn parse(input: &str) -> IResult<&str, Self> {
+        binary_level(input, "+-", parse_product)
+    }
+}
+
+fn parse_product(input: &str) -> IResult<&str, Operation> {
+    binary_level(input, "*/", parse_factor)
+}
+
+fn parse_factor(input: &str) -> IResult<&str, Operation> {
+    let (input, _) = space0(input)?;
+    alt((
+        map(tag("old"), |_| Operation::Old),
+        map(nom::character::complete::u64, Operation::Literal),
+        delimited(tag("("), Operation::parse, preceded(space0, tag(")"))),
+    ))(input)
+}
+
+fn binary_level<'a>(
+    input: &'a str,
+    operators: &'static str,
+    operand: fn(&str) -> IResult<&str, Operation>,
+) -> IResult<&'a str, Operation> {
+    let (input, first) = operand(input)?;
+    fold_many0(
+        pair(preceded(space0, one_of(operators)), operand),
+        move || first.clone(),
+        |lhs, (operator, rhs)| {
+            let operator = match operator {
+                '+' => Operator::Add,
+                '-' => Operator::Subtract,
+                '*' => Operator::Multiply,
+                _ => Operator::Divide,
+            };
+            Operation::Binary(Box::new(lhs), operator, Box::new(rhs))
+        },
+    )(input)
+}
+
+impl Operation {
+    fn apply<B: WorryBackend>(&self, backend: &B, old: &B::Level) -> Option<B::Level> {
+        match self {
+            Operation::Old => Some(old.clone()),
+            Operation::Literal(val) => Some(backend.level(*val)),
+            Operation::Binary(lhs, operator, rhs) => {
+                let lhs = lhs.apply(backend, old)?;
+                let rhs = rhs.apply(backend, old)?;
+                match operator {
+                    Operator::Add => backend.add(&lhs, &rhs),
+                    Operator::Subtract => backend.subtract(&lhs, &rhs),
+                    Operator::Multiply => backend.multiply(&lhs, &rhs),
+                    Operator::Divide => backend.divide(&lhs, &rhs),
+                }
+            }
+        }
+    }
+
+    // whether the result modulo the LCM only depends on `old` modulo the LCM
+    // which holds for everything except division, and for subtraction only as long as the real
+    // value never goes negative, which can't be told from the reduced one
+    fn is_modular_safe(&self) -> bool {
+        match self {
+            Operation::Binary(_, Operator::Divide | Operator::Subtract, _) => false,
+            Operation::Binary(lhs, _, rhs) => lhs.is_modular_safe() && rhs.is_modular_safe(),
+            Operation::Old | Operation::Literal(_) => true,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum SimulationError {
+    // the monkey at this position in the input, counting from 0 like the input does, didn't
+    // parse; the line is where parsing stopped, counting from 1
+    Parse {
+        monkey: usize,
+        line: usize,
+    },
+    UnknownMonkey {
+        monkey: usize,
+        target: usize,
+    },
+    // the worry level left the range the backend can hold: it overflowed,
+    // went negative or was divided by zero
+    Overflow {
+        round: u64,
+        monkey: usize,
+        worry: String,
+    },
+    NotModularSafe {
+        monkey: usize,
+    },
+    // only reduced worry levels without relief are sure to repeat, anything else can keep
+    // growing and every round would have to be remembered
+    CycleDetectionNeedsModular,
+    // the product of the top monkeys' counts doesn't fit in a u128
+    ScoreOverflow {
+        top_k: usize,
+    },
+}
+
+impl fmt::Display for SimulationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SimulationError::Parse { monkey, line } => {
+                write!(f, "couldn't parse monkey {} at line {}", monkey, line)
+            }
+            SimulationError::UnknownMonkey { monkey, target } => write!(
+                f,
+                "monkey {} throws to monkey {}, which doesn't exist",
+                monkey, target
+            ),
+            SimulationError::Overflow {
+                round,
+                monkey,
+                worry,
+            } => write!(
+                f,
+                "worry level {} went out of range when monkey {} inspected it in round {}",
+                worry, monkey, round
+            ),
+            SimulationError::NotModularSafe { monkey } => write!(
+                f,
+                "monkey {} divides or subtracts, which can't be done modulo the LCM",
+                monkey
+            ),
+            SimulationError::CycleDetectionNeedsModular => write!(
+                f,
+                "cycle detection needs modular worry levels and a relief divisor of 1"
+            ),
+            SimulationError::ScoreOverflow { top_k } => write!(
+                f,
+                "the monkey business score of the top {} monkeys is too large",
+                top_k
+            ),
+        }
+    }
 }
 
+impl std::error::Error for SimulationError {}
+
 #[derive(Debug, PartialEq, Clone)]
 struct TestResultTargetMonkey {
     when_true: usize,
@@ -156,105 +316,336 @@ impl Parseable for TestResultTargetMonkey {
     }
 }
 
-fn parse_monkeys(input: &str) -> Result<Monkeys, Box<dyn std::error::Error + '_>> {
-    let (input, monkeys) = many0(Monkey::parse)(input)?;
-    return Ok(monkeys);
+// every monkey in the input has to parse, and every monkey they throw to has to exist
+fn parse_monkeys(input: &str) -> Result<Monkeys, SimulationError> {
+    let line = |rest: &str| input[..input.len() - rest.len()].matches('\n').count() + 1;
+    let mut monkeys = vec![];
+    let mut rest = input;
+    while !rest.trim_start().is_empty() {
+        match Monkey::parse(rest) {
+            Ok((after, monkey)) => {
+                monkeys.push(monkey);
+                rest = after;
+            }
+            Err(nom::Err::Error(e) | nom::Err::Failure(e)) => {
+                return Err(SimulationError::Parse {
+                    monkey: monkeys.len(),
+                    line: line(e.input),
+                })
+            }
+            Err(nom::Err::Incomplete(_)) => {
+                return Err(SimulationError::Parse {
+                    monkey: monkeys.len(),
+                    line: line(""),
+                })
+            }
+        }
+    }
+    for monkey in &monkeys {
+        let targets = &monkey.test_result_monkey;
+        for target in [targets.when_true, targets.when_false] {
+            if target >= monkeys.len() {
+                return Err(SimulationError::UnknownMonkey {
+                    monkey: monkey.number,
+                    target,
+                });
+            }
+        }
+    }
+    Ok(monkeys)
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Strategy {
+    // every monkey takes its turn every round
+    RoundByRound,
+    // each item is followed until its state repeats and the rest is extrapolated
+    CycleDetection,
+    // the items are split across this many threads and followed independently
+    Parallel { threads: usize },
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Scoring {
+    Product,
+    Sum,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct SimulationConfig {
+    pub rounds: u64,
+    // worry levels are divided by this after every inspection
+    pub relief_divisor: u64,
+    // how many of the most active monkeys go into the monkey business score
+    pub top_k: usize,
+    pub scoring: Scoring,
+    pub mode: WorryMode,
+    pub strategy: Strategy,
+}
+
+impl SimulationConfig {
+    pub fn part1() -> Self {
+        SimulationConfig {
+            rounds: 20,
+            relief_divisor: 3,
+            top_k: 2,
+            scoring: Scoring::Product,
+            mode: WorryMode::Modular,
+            strategy: Strategy::RoundByRound,
+        }
+    }
+
+    pub fn part2() -> Self {
+        SimulationConfig {
+            rounds: 10000,
+            relief_divisor: 1,
+            ..Self::part1()
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct MonkeyStats {
+    pub number: usize,
+    pub num_inspected: usize,
+    // 1 for the most active monkey, ties keep their monkey order
+    pub rank: usize,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct SimulationResult {
+    // in monkey order
+    pub monkeys: Vec<MonkeyStats>,
+    pub total_inspected: usize,
+    pub monkey_business: u128,
+}
+
+impl SimulationResult {
+    fn new(
+        num_inspected: Vec<usize>,
+        top_k: usize,
+        scoring: Scoring,
+    ) -> Result<Self, SimulationError> {
+        let mut by_activity: Vec<usize> = (0..num_inspected.len()).collect();
+        by_activity.sort_by(|a, b| num_inspected[*b].cmp(&num_inspected[*a]));
+        let mut monkeys: Vec<MonkeyStats> = num_inspected
+            .iter()
+            .enumerate()
+            .map(|(number, num_inspected)| MonkeyStats {
+                number,
+                num_inspected: *num_inspected,
+                rank: 0,
+            })
+            .collect();
+        for (rank, number) in by_activity.iter().enumerate() {
+            monkeys[*number].rank = rank + 1;
+        }
+        let mut top = by_activity
+            .iter()
+            .take(top_k)
+            .map(|number| num_inspected[*number] as u128);
+        let monkey_business = match scoring {
+            Scoring::Product => top
+                .try_fold(1u128, |product, count| product.checked_mul(count))
+                .ok_or(SimulationError::ScoreOverflow { top_k })?,
+            Scoring::Sum => top.sum(),
+        };
+        Ok(SimulationResult {
+            monkeys,
+            total_inspected: num_inspected.iter().sum(),
+            monkey_business,
+        })
+    }
+}
+
+pub fn simulation(
+    input: &str,
+    config: &SimulationConfig,
+) -> Result<SimulationResult, SimulationError> {
+    let num_inspected = simulate(input, config, None)?;
+    SimulationResult::new(num_inspected, config.top_k, config.scoring)
+}
+
+// runs the simulation round by round whatever the configured strategy,
+// recording every monkey after each round
+pub fn trace_simulation(
+    input: &str,
+    config: &SimulationConfig,
+) -> Result<SimulationTrace, SimulationError> {
+    let mut trace = SimulationTrace::default();
+    let config = SimulationConfig {
+        strategy: Strategy::RoundByRound,
+        ..*config
+    };
+    simulate(input, &config, Some(&mut trace))?;
+    Ok(trace)
 }
 
-fn print_monkeys(monkeys: &Monkeys) {
-    for monkey in monkeys.iter() {
-        println!("{}", monkey);
+fn simulate(
+    input: &str,
+    config: &SimulationConfig,
+    trace: Option<&mut SimulationTrace>,
+) -> Result<Vec<usize>, SimulationError> {
+    let monkeys = parse_monkeys(input)?;
+    let SimulationConfig {
+        rounds,
+        relief_divisor,
+        strategy,
+        ..
+    } = *config;
+    if strategy == Strategy::CycleDetection
+        && (config.mode != WorryMode::Modular || relief_divisor != 1)
+    {
+        return Err(SimulationError::CycleDetectionNeedsModular);
+    }
+    match config.mode {
+        WorryMode::Exact => run(monkeys, rounds, relief_divisor, &Exact, strategy, trace),
+        WorryMode::Modular => {
+            if relief_divisor == 1 {
+                if let Some(monkey) = monkeys
+                    .iter()
+                    .find(|monkey| !monkey.operation.is_modular_safe())
+                {
+                    return Err(SimulationError::NotModularSafe {
+                        monkey: monkey.number,
+                    });
+                }
+            }
+            let divisors = monkeys.iter().map(|monkey| match monkey.test {
+                Test::DivisibleBy(val) => val,
+            });
+            let backend = Modular::new(divisors, relief_divisor);
+            run(monkeys, rounds, relief_divisor, &backend, strategy, trace)
+        }
     }
 }
 
-pub fn simulation(input: &str, num_rounds: u64, worry_divisor: u64) -> u64 {
-    let mut monkeys = parse_monkeys(input).unwrap();
-    println!("-------- Round 0 ---------");
-    // print_monkeys(&monkeys);
+// returns how many items each monkey inspected
+fn run<B: WorryBackend>(
+    monkeys: Monkeys,
+    num_rounds: u64,
+    worry_divisor: u64,
+    backend: &B,
+    strategy: Strategy,
+    mut trace: Option<&mut SimulationTrace>,
+) -> Result<Vec<usize>, SimulationError> {
+    let mut monkeys: Monkeys<B::Level> = monkeys
+        .into_iter()
+        .map(|monkey| monkey.with_backend(backend))
+        .collect();
+    // the per-item strategies never have every monkey's items at hand, so they can't be traced
+    match strategy {
+        Strategy::RoundByRound => {}
+        Strategy::CycleDetection => {
+            return cycles::run(&monkeys, num_rounds, worry_divisor, backend)
+        }
+        Strategy::Parallel { threads } => {
+            return parallel::run(&monkeys, num_rounds, worry_divisor, backend, threads)
+        }
+    }
     for round_number in 1..=num_rounds {
-        // println!("-------- Round {} ---------", round_number);
-        round(&mut monkeys, worry_divisor);
-        // print_monkeys(&monkeys);
-    }
-    let mut sorted_inspections = monkeys
-        .iter()
-        .map(|monkey| monkey.num_inspected)
-        .collect::<Vec<_>>();
-    sorted_inspections.sort();
-    sorted_inspections.reverse();
-    println!("{:?}", sorted_inspections);
-    (sorted_inspections[0] as u64 * sorted_inspections[1] as u64) as u64
-}
-
-fn round(monkeys: &mut Monkeys, worry_divisor: u64) {
-    // for part 2 this requires some modulo math in order to keep the worry number low enough to fit
-    // into a 32- (or even 64-) bit uint
-    // the trick here is to find the least common multiple of all the monkeys' test numbers
-    // and then modulo the worry number by that
-    // The reason this works is because the test is always a modulo test, so if the worry number
-    // is divisible by the test number, then the worry number modulo the LCM will also be the same
-
-    // e.g.
-    // divisor tests: 3, 5, 7
-    // item worry level: 123645
-    // LCM of 3, 5, 7 = 105
-    // 123645 % 3 = 0
-    // 123645 % 5 = 0
-    // 123645 % 7 = 4
-
-    // mod the big number by the LCM of all the divisors
-    // 123645 % 105 = 60
-    // 60 % 3 = 0
-    // 60 % 5 = 0
-    // 60 % 7 = 4
-    // since we get the same remainder, we can use this trick to keep the worry number small
-    let lcm_divisor = monkeys.iter().fold(1, |acc, monkey| match monkey.test {
-        Test::DivisibleBy(val) => acc * val,
-    });
-    for i in 0..monkeys.len() {
-        let mc;
-        {
-            let monkey = &mut monkeys[i];
-            mc = monkey.clone();
-            monkey.num_inspected += monkey.items.len();
+        round(&mut monkeys, worry_divisor, backend)
+            .map_err(|overflow| overflow.in_round(round_number))?;
+        if let Some(trace) = trace.as_deref_mut() {
+            trace.record(round_number, &monkeys);
+        }
+    }
+    Ok(monkeys.iter().map(|monkey| monkey.num_inspected).collect())
+}
+
+// the monkey whose operation overflowed and the worry level it started from
+#[derive(Debug, PartialEq)]
+struct Overflow {
+    monkey: usize,
+    worry: String,
+}
+
+impl Overflow {
+    fn in_round(self, round: u64) -> SimulationError {
+        SimulationError::Overflow {
+            round,
+            monkey: self.monkey,
+            worry: self.worry,
         }
+    }
+}
+
+fn round<B: WorryBackend>(
+    monkeys: &mut Monkeys<B::Level>,
+    worry_divisor: u64,
+    backend: &B,
+) -> Result<(), Overflow> {
+    for i in 0..monkeys.len() {
+        monkeys[i].num_inspected += monkeys[i].items.len();
         while let Some(item) = monkeys[i].items.pop_front() {
-            let mut new_item = item.clone();
-            let new_item_clone = new_item.clone();
-            new_item.worry = match &mc.operation {
-                Operation::Add(val) => item.worry + val,
-                Operation::Multiply(val) => item.worry * val,
-                Operation::Square => item.worry * item.worry,
-            } % lcm_divisor
-                / worry_divisor;
-            match mc.test {
-                Test::DivisibleBy(val) => {
-                    let destination_monkey: usize;
-                    if new_item.worry % val == 0 {
-                        destination_monkey = mc.test_result_monkey.when_true;
-                    } else {
-                        destination_monkey = mc.test_result_monkey.when_false;
-                    }
-                    monkeys[destination_monkey].items.push_back(new_item);
-
-                    // println!(
-                    //     "Monkey {} inspected item with worry {}. New item is {}. Throwing to monkey {}",
-                    //     mc.number,
-                    //     item.worry,
-                    //     new_item_clone.worry,
-                    //     destination_monkey
-                    // );
-                }
+            let (destination_monkey, worry) =
+                throw(monkeys, i, &item.worry, worry_divisor, backend)?;
+            monkeys[destination_monkey].items.push_back(Item { worry });
+        }
+    }
+    Ok(())
+}
+
+// where a monkey throws an item with the given worry level and what its worry level is then
+fn throw<B: WorryBackend>(
+    monkeys: &Monkeys<B::Level>,
+    holder: usize,
+    worry: &B::Level,
+    worry_divisor: u64,
+    backend: &B,
+) -> Result<(usize, B::Level), Overflow> {
+    let monkey = &monkeys[holder];
+    let worry = monkey
+        .operation
+        .apply(backend, worry)
+        .ok_or_else(|| Overflow {
+            monkey: holder,
+            worry: worry.to_string(),
+        })?;
+    let worry = backend.relieve(&worry, worry_divisor);
+    let target = match monkey.test {
+        Test::DivisibleBy(val) => {
+            if backend.is_divisible_by(&worry, val) {
+                monkey.test_result_monkey.when_true
+            } else {
+                monkey.test_result_monkey.when_false
             }
         }
+    };
+    Ok((target, worry))
+}
+
+// follows a single item through one round, counting its inspections, and returns where it
+// starts the next round: an item thrown to a later monkey gets inspected again in the same round
+fn item_round<B: WorryBackend>(
+    monkeys: &Monkeys<B::Level>,
+    mut holder: usize,
+    mut worry: B::Level,
+    worry_divisor: u64,
+    backend: &B,
+    num_inspected: &mut [usize],
+) -> Result<(usize, B::Level), Overflow> {
+    loop {
+        num_inspected[holder] += 1;
+        let (target, new_worry) = throw(monkeys, holder, &worry, worry_divisor, backend)?;
+        let next_round = target <= holder;
+        holder = target;
+        worry = new_worry;
+        if next_round {
+            return Ok((holder, worry));
+        }
     }
 }
 
-pub fn part1(input: &str) -> u64 {
-    simulation(input, 20, 3)
+pub fn part1(input: &str) -> u128 {
+    simulation(input, &SimulationConfig::part1())
+        .unwrap()
+        .monkey_business
 }
-pub fn part2(input: &str) -> u64 {
-    simulation(input, 10000, 1)
+pub fn part2(input: &str) -> u128 {
+    simulation(input, &SimulationConfig::part2())
+        .unwrap()
+        .monkey_business
 }
 #[cfg(test)]
 mod tests {
@@ -288,6 +679,10 @@ Monkey 3:
     If true: throw to monkey 0
     If false: throw to monkey 1"#;
 
+    fn binary(lhs: Operation, operator: Operator, rhs: Operation) -> Operation {
+        Operation::Binary(Box::new(lhs), operator, Box::new(rhs))
+    }
+
     #[test]
     fn test_parse_test() {
         let input = "  Test: divisible by 3";
@@ -307,6 +702,58 @@ Monkey 3:
         assert_eq!(test_result_monkey, expected);
     }
 
+    #[test]
+    fn test_parse_operation() {
+        let (rest, actual) = Operation::parse("old + old").unwrap();
+        assert_eq!(rest, "");
+        assert_eq!(
+            actual,
+            binary(Operation::Old, Operator::Add, Operation::Old)
+        );
+
+        // * binds tighter than -, and - is left associative
+        let (_, actual) = Operation::parse("old - 2 * old - 1").unwrap();
+        let expected = binary(
+            binary(
+                Operation::Old,
+                Operator::Subtract,
+                binary(Operation::Literal(2), Operator::Multiply, Operation::Old),
+            ),
+            Operator::Subtract,
+            Operation::Literal(1),
+        );
+        assert_eq!(actual, expected);
+
+        let (_, actual) = Operation::parse("(old + 1) / 2").unwrap();
+        let expected = binary(
+            binary(Operation::Old, Operator::Add, Operation::Literal(1)),
+            Operator::Divide,
+            Operation::Literal(2),
+        );
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_parse_operation_stops_at_line_end() {
+        let (rest, _) = Operation::parse("old * 19\n  Test: divisible by 23").unwrap();
+        assert_eq!(rest, "\n  Test: divisible by 23");
+        assert!(Operation::parse("(old + 1").is_err());
+    }
+
+    #[test]
+    fn test_apply_operation() {
+        let (_, operation) = Operation::parse("(old + 3) * (old - 1) / 2").unwrap();
+        assert_eq!(
+            operation.apply(&Exact, &Exact.level(5)),
+            Some(Exact.level(16))
+        );
+        assert!(!operation.is_modular_safe());
+        let (_, operation) = Operation::parse("old * old - old + 7").unwrap();
+        assert!(!operation.is_modular_safe());
+        let (_, operation) = Operation::parse("old * old + old + 7").unwrap();
+        assert!(operation.is_modular_safe());
+    }
+
     #[test]
     fn test_parse_monkey() {
         let input = r#"Monkey 0:
@@ -320,7 +767,7 @@ Monkey 3:
             number: 0,
             num_inspected: 0,
             items: VecDeque::from(vec![Item { worry: 79 }, Item { worry: 98 }]),
-            operation: Operation::Multiply(19),
+            operation: binary(Operation::Old, Operator::Multiply, Operation::Literal(19)),
             test: Test::DivisibleBy(23),
             test_result_monkey: TestResultTargetMonkey {
                 when_true: 2,
@@ -337,7 +784,7 @@ Monkey 3:
             Monkey {
                 num_inspected: 0,
                 items: VecDeque::from(vec![Item { worry: 79 }, Item { worry: 98 }]),
-                operation: Operation::Multiply(19),
+                operation: binary(Operation::Old, Operator::Multiply, Operation::Literal(19)),
                 test: Test::DivisibleBy(23),
                 test_result_monkey: TestResultTargetMonkey {
                     when_true: 2,
@@ -352,7 +799,7 @@ Monkey 3:
                     Item { worry: 75 },
                     Item { worry: 74 },
                 ]),
-                operation: Operation::Add(6),
+                operation: binary(Operation::Old, Operator::Add, Operation::Literal(6)),
                 test: Test::DivisibleBy(19),
                 test_result_monkey: TestResultTargetMonkey {
                     when_true: 2,
@@ -367,7 +814,7 @@ Monkey 3:
                     Item { worry: 60 },
                     Item { worry: 97 },
                 ]),
-                operation: Operation::Square,
+                operation: binary(Operation::Old, Operator::Multiply, Operation::Old),
                 test: Test::DivisibleBy(13),
                 test_result_monkey: TestResultTargetMonkey {
                     when_true: 1,
@@ -378,7 +825,7 @@ Monkey 3:
             },
             Monkey {
                 items: VecDeque::from(vec![Item { worry: 74 }]),
-                operation: Operation::Add(3),
+                operation: binary(Operation::Old, Operator::Add, Operation::Literal(3)),
                 test: Test::DivisibleBy(17),
                 test_result_monkey: TestResultTargetMonkey {
                     when_true: 0,
@@ -391,9 +838,305 @@ Monkey 3:
         assert_eq!(actual, expected);
     }
 
+    fn monkey_business(input: &str, config: SimulationConfig) -> Result<u128, SimulationError> {
+        simulation(input, &config).map(|result| result.monkey_business)
+    }
+
     #[test]
     fn test_simulation() {
-        let actual = simulation(INPUT, NUM_ROUNDS as u64, 3);
-        assert_eq!(actual, 10605);
+        let actual = monkey_business(INPUT, SimulationConfig::part1());
+        assert_eq!(actual, Ok(10605));
+    }
+
+    #[test]
+    fn test_simulation_part2() {
+        let actual = monkey_business(INPUT, SimulationConfig::part2());
+        assert_eq!(actual, Ok(2713310158));
+    }
+
+    #[test]
+    fn test_simulation_result() {
+        let actual = simulation(INPUT, &SimulationConfig::part1()).unwrap();
+        let ranks: Vec<(usize, usize)> = actual
+            .monkeys
+            .iter()
+            .map(|monkey| (monkey.num_inspected, monkey.rank))
+            .collect();
+        assert_eq!(ranks, vec![(101, 2), (95, 3), (7, 4), (105, 1)]);
+        assert_eq!(actual.total_inspected, 308);
+    }
+
+    #[test]
+    fn test_scoring() {
+        let config = SimulationConfig {
+            top_k: 3,
+            scoring: Scoring::Sum,
+            ..SimulationConfig::part1()
+        };
+        assert_eq!(monkey_business(INPUT, config), Ok(105 + 101 + 95));
+        let config = SimulationConfig {
+            top_k: 1,
+            ..SimulationConfig::part1()
+        };
+        assert_eq!(monkey_business(INPUT, config), Ok(105));
+    }
+
+    #[test]
+    fn test_exact_matches_modular() {
+        for config in [
+            SimulationConfig::part1(),
+            SimulationConfig {
+                rounds: 20,
+                ..SimulationConfig::part2()
+            },
+        ] {
+            let exact = simulation(
+                INPUT,
+                &SimulationConfig {
+                    mode: WorryMode::Exact,
+                    ..config
+                },
+            );
+            assert_eq!(exact, simulation(INPUT, &config));
+        }
+    }
+
+    #[test]
+    fn test_division_is_not_modular_safe() {
+        let input = INPUT.replace("new = old + 6", "new = (old + 6) / 2");
+        assert_eq!(
+            simulation(&input, &SimulationConfig::part2()),
+            Err(SimulationError::NotModularSafe { monkey: 1 })
+        );
+        // with relief the values aren't reduced, so division is fine
+        let exact = SimulationConfig {
+            mode: WorryMode::Exact,
+            ..SimulationConfig::part1()
+        };
+        assert_eq!(
+            simulation(&input, &SimulationConfig::part1()),
+            simulation(&input, &exact)
+        );
+    }
+
+    #[test]
+    fn test_subtraction_is_not_modular_safe() {
+        let input = INPUT.replace("new = old + 6", "new = old - 100");
+        assert_eq!(
+            simulation(&input, &SimulationConfig::part2()),
+            Err(SimulationError::NotModularSafe { monkey: 1 })
+        );
+        // the exact values go negative, which is reported rather than wrapped around
+        let exact = SimulationConfig {
+            mode: WorryMode::Exact,
+            ..SimulationConfig::part2()
+        };
+        assert!(matches!(
+            simulation(&input, &exact),
+            Err(SimulationError::Overflow { monkey: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn test_parse_errors_are_reported() {
+        // an unsupported operator used to end the list early and drop monkeys 1 to 3
+        let input = INPUT.replace("new = old + 6", "new = old ^ 6");
+        let actual = simulation(&input, &SimulationConfig::part1());
+        assert_eq!(
+            actual,
+            Err(SimulationError::Parse {
+                monkey: 1,
+                line: 10
+            })
+        );
+        assert_eq!(
+            actual.unwrap_err().to_string(),
+            "couldn't parse monkey 1 at line 10"
+        );
+
+        let input = format!("{}\n\nMonkey 4:\n  Starting items: x", INPUT);
+        assert_eq!(
+            simulation(&input, &SimulationConfig::part1()),
+            Err(SimulationError::Parse {
+                monkey: 4,
+                line: 30
+            })
+        );
+        // trailing blank lines are fine
+        let input = format!("{}\n\n", INPUT);
+        assert!(simulation(&input, &SimulationConfig::part1()).is_ok());
+    }
+
+    #[test]
+    fn test_unknown_target_is_reported() {
+        let input = INPUT.replacen("throw to monkey 3", "throw to monkey 7", 1);
+        assert_eq!(
+            simulation(&input, &SimulationConfig::part1()),
+            Err(SimulationError::UnknownMonkey {
+                monkey: 0,
+                target: 7
+            })
+        );
+    }
+
+    #[test]
+    fn test_overflow_is_reported() {
+        // without relief or modular reduction the squares blow past u64 within a few rounds
+        let config = SimulationConfig {
+            relief_divisor: 2,
+            ..SimulationConfig::part1()
+        };
+        let actual = simulation(INPUT, &config);
+        assert!(matches!(actual, Err(SimulationError::Overflow { .. })));
+    }
+
+    #[test]
+    fn test_cycle_detection_matches_round_by_round() {
+        let monkeys = parse_monkeys(INPUT).unwrap();
+        let divisors = monkeys.iter().map(|monkey| match monkey.test {
+            Test::DivisibleBy(val) => val,
+        });
+        let backend = Modular::new(divisors, 1);
+        for num_rounds in [1, 2, 20, 500, 1000] {
+            let naive = run(
+                monkeys.clone(),
+                num_rounds,
+                1,
+                &backend,
+                Strategy::RoundByRound,
+                None,
+            );
+            let fast = run(
+                monkeys.clone(),
+                num_rounds,
+                1,
+                &backend,
+                Strategy::CycleDetection,
+                None,
+            );
+            assert_eq!(naive, fast, "after {} rounds", num_rounds);
+        }
+        // the cycles found within a short run are still right without the modular backend, even
+        // though simulation() won't risk it
+        let naive = run(monkeys.clone(), 20, 3, &Exact, Strategy::RoundByRound, None);
+        let fast = run(monkeys, 20, 3, &Exact, Strategy::CycleDetection, None);
+        assert_eq!(naive, fast);
+    }
+
+    #[test]
+    fn test_trace_held_items() {
+        let trace = trace_simulation(INPUT, &SimulationConfig::part1()).unwrap();
+        assert_eq!(trace.rounds.len(), 20);
+        let expected = r#"After round 1, the monkeys are holding items with these worry levels:
+Monkey 0: 20, 23, 27, 26
+Monkey 1: 2080, 25, 167, 207, 401, 1046
+Monkey 2:
+Monkey 3:
+"#;
+        assert_eq!(trace.rounds[0].held_items(), expected);
+        let expected = r#"After round 20, the monkeys are holding items with these worry levels:
+Monkey 0: 10, 12, 14, 26, 34
+Monkey 1: 245, 93, 53, 199, 115
+Monkey 2:
+Monkey 3:
+"#;
+        assert_eq!(trace.rounds[19].held_items(), expected);
+    }
+
+    #[test]
+    fn test_trace_inspections() {
+        let config = SimulationConfig {
+            rounds: 20,
+            ..SimulationConfig::part2()
+        };
+        let trace = trace_simulation(INPUT, &config).unwrap();
+        let expected = r#"== After round 20 ==
+Monkey 0 inspected items 99 times.
+Monkey 1 inspected items 97 times.
+Monkey 2 inspected items 8 times.
+Monkey 3 inspected items 103 times.
+"#;
+        assert_eq!(trace.rounds[19].inspections(), expected);
+    }
+
+    #[test]
+    fn test_parallel_matches_round_by_round() {
+        for config in [SimulationConfig::part1(), SimulationConfig::part2()] {
+            let expected = simulation(INPUT, &config).unwrap();
+            // more threads than items leaves some of them empty handed
+            for threads in [1, 3, 4, 64] {
+                let parallel = SimulationConfig {
+                    strategy: Strategy::Parallel { threads },
+                    ..config
+                };
+                assert_eq!(simulation(INPUT, &parallel).unwrap(), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_parallel_reports_overflow() {
+        let config = SimulationConfig {
+            relief_divisor: 2,
+            strategy: Strategy::Parallel { threads: 2 },
+            ..SimulationConfig::part1()
+        };
+        let actual = simulation(INPUT, &config);
+        assert!(matches!(actual, Err(SimulationError::Overflow { .. })));
+    }
+
+    #[test]
+    fn test_cycle_detection_long_run() {
+        let config = SimulationConfig {
+            strategy: Strategy::CycleDetection,
+            ..SimulationConfig::part2()
+        };
+        assert_eq!(monkey_business(INPUT, config), Ok(2713310158));
+        // a billion rounds would take hours one by one
+        let config = SimulationConfig {
+            rounds: 1_000_000_000,
+            ..config
+        };
+        assert!(monkey_business(INPUT, config).is_ok());
+    }
+
+    #[test]
+    fn test_cycle_detection_needs_modular() {
+        for config in [
+            SimulationConfig {
+                mode: WorryMode::Exact,
+                ..SimulationConfig::part2()
+            },
+            SimulationConfig::part1(),
+        ] {
+            let config = SimulationConfig {
+                strategy: Strategy::CycleDetection,
+                ..config
+            };
+            assert_eq!(
+                simulation(INPUT, &config),
+                Err(SimulationError::CycleDetectionNeedsModular)
+            );
+        }
+    }
+
+    #[test]
+    fn test_score_overflow_is_reported() {
+        // more monkeys than there are is fine, every one of them counts
+        let config = SimulationConfig {
+            rounds: 1_000_000_000_000,
+            top_k: 8,
+            strategy: Strategy::CycleDetection,
+            ..SimulationConfig::part2()
+        };
+        assert_eq!(
+            monkey_business(INPUT, config),
+            Err(SimulationError::ScoreOverflow { top_k: 8 })
+        );
+        let config = SimulationConfig {
+            scoring: Scoring::Sum,
+            ..config
+        };
+        assert!(monkey_business(INPUT, config).is_ok());
     }
 }
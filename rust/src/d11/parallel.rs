@@ -0,0 +1,69 @@
+use std::thread;
+
+use super::{item_round, worry::WorryBackend, Monkeys, SimulationError};
+
+// items never interact, so they can be split across threads, followed through every round on
+// their own and the inspection counts added up at the end
+pub fn run<B: WorryBackend>(
+    monkeys: &Monkeys<B::Level>,
+    num_rounds: u64,
+    worry_divisor: u64,
+    backend: &B,
+    threads: usize,
+) -> Result<Vec<usize>, SimulationError> {
+    let items: Vec<(usize, B::Level)> = monkeys
+        .iter()
+        .enumerate()
+        .flat_map(|(holder, monkey)| {
+            monkey
+                .items
+                .iter()
+                .map(move |item| (holder, item.worry.clone()))
+        })
+        .collect();
+    let chunk_size = items.len().div_ceil(threads.max(1)).max(1);
+    thread::scope(|scope| {
+        let handles: Vec<_> = items
+            .chunks(chunk_size)
+            .map(|chunk| {
+                scope.spawn(move || {
+                    simulate_items(monkeys, chunk, num_rounds, worry_divisor, backend)
+                })
+            })
+            .collect();
+        let mut num_inspected = vec![0; monkeys.len()];
+        // joined in order so an error is always reported from the earliest chunk
+        for handle in handles {
+            let counts = handle.join().unwrap()?;
+            for (total, count) in num_inspected.iter_mut().zip(counts) {
+                *total += count;
+            }
+        }
+        Ok(num_inspected)
+    })
+}
+
+fn simulate_items<B: WorryBackend>(
+    monkeys: &Monkeys<B::Level>,
+    items: &[(usize, B::Level)],
+    num_rounds: u64,
+    worry_divisor: u64,
+    backend: &B,
+) -> Result<Vec<usize>, SimulationError> {
+    let mut num_inspected = vec![0; monkeys.len()];
+    for (holder, worry) in items {
+        let (mut holder, mut worry) = (*holder, worry.clone());
+        for round_number in 1..=num_rounds {
+            (holder, worry) = item_round(
+                monkeys,
+                holder,
+                worry,
+                worry_divisor,
+                backend,
+                &mut num_inspected,
+            )
+            .map_err(|overflow| overflow.in_round(round_number))?;
+        }
+    }
+    Ok(num_inspected)
+}
@@ -0,0 +1,156 @@
+use std::fmt;
+
+use super::Monkeys;
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct MonkeySnapshot {
+    pub number: usize,
+    // worry levels as the backend prints them, exact values can be too big for a u64
+    pub items: Vec<String>,
+    pub num_inspected: usize,
+}
+
+// the state of every monkey at the end of a round
+#[derive(Debug, PartialEq, Clone)]
+pub struct RoundSnapshot {
+    pub round: u64,
+    pub monkeys: Vec<MonkeySnapshot>,
+}
+
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct SimulationTrace {
+    pub rounds: Vec<RoundSnapshot>,
+}
+
+impl SimulationTrace {
+    pub(super) fn record<W: fmt::Display>(&mut self, round: u64, monkeys: &Monkeys<W>) {
+        self.rounds.push(RoundSnapshot {
+            round,
+            monkeys: monkeys
+                .iter()
+                .map(|monkey| MonkeySnapshot {
+                    number: monkey.number,
+                    items: monkey
+                        .items
+                        .iter()
+                        .map(|item| item.worry.to_string())
+                        .collect(),
+                    num_inspected: monkey.num_inspected,
+                })
+                .collect(),
+        });
+    }
+
+    // one row per monkey per round, items separated by spaces
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("round,monkey,num_inspected,items\n");
+        for snapshot in &self.rounds {
+            for monkey in &snapshot.monkeys {
+                csv.push_str(&format!(
+                    "{},{},{},{}\n",
+                    snapshot.round,
+                    monkey.number,
+                    monkey.num_inspected,
+                    monkey.items.join(" ")
+                ));
+            }
+        }
+        csv
+    }
+
+    pub fn to_json(&self) -> String {
+        let rounds: Vec<String> = self
+            .rounds
+            .iter()
+            .map(|snapshot| {
+                let monkeys: Vec<String> = snapshot
+                    .monkeys
+                    .iter()
+                    .map(|monkey| {
+                        format!(
+                            r#"{{"monkey":{},"num_inspected":{},"items":[{}]}}"#,
+                            monkey.number,
+                            monkey.num_inspected,
+                            monkey.items.join(",")
+                        )
+                    })
+                    .collect();
+                format!(
+                    r#"{{"round":{},"monkeys":[{}]}}"#,
+                    snapshot.round,
+                    monkeys.join(",")
+                )
+            })
+            .collect();
+        format!("[{}]", rounds.join(","))
+    }
+}
+
+impl RoundSnapshot {
+    // same layout as the puzzle text
+    pub fn held_items(&self) -> String {
+        let mut summary = format!(
+            "After round {}, the monkeys are holding items with these worry levels:\n",
+            self.round
+        );
+        for monkey in &self.monkeys {
+            summary.push_str(&format!("Monkey {}:", monkey.number));
+            if !monkey.items.is_empty() {
+                summary.push_str(&format!(" {}", monkey.items.join(", ")));
+            }
+            summary.push('\n');
+        }
+        summary
+    }
+
+    pub fn inspections(&self) -> String {
+        let mut summary = format!("== After round {} ==\n", self.round);
+        for monkey in &self.monkeys {
+            summary.push_str(&format!(
+                "Monkey {} inspected items {} times.\n",
+                monkey.number, monkey.num_inspected
+            ));
+        }
+        summary
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trace() -> SimulationTrace {
+        SimulationTrace {
+            rounds: vec![RoundSnapshot {
+                round: 1,
+                monkeys: vec![
+                    MonkeySnapshot {
+                        number: 0,
+                        items: vec!["20".to_string(), "23".to_string()],
+                        num_inspected: 2,
+                    },
+                    MonkeySnapshot {
+                        number: 1,
+                        items: vec![],
+                        num_inspected: 4,
+                    },
+                ],
+            }],
+        }
+    }
+
+    #[test]
+    fn test_to_csv() {
+        let expected = r#"round,monkey,num_inspected,items
+1,0,2,20 23
+1,1,4,
+"#;
+        assert_eq!(trace().to_csv(), expected);
+    }
+
+    #[test]
+    fn test_to_json() {
+        let expected = r#"[{"round":1,"monkeys":[{"monkey":0,"num_inspected":2,"items":[20,23]},{"monkey":1,"num_inspected":4,"items":[]}]}]"#;
+        assert_eq!(trace().to_json(), expected);
+    }
+}
@@ -0,0 +1,190 @@
+use std::{fmt, hash::Hash};
+
+use num::{BigUint, Integer, Zero};
+
+// how worry levels are stored and combined for the length of one simulation
+// the arithmetic returns None instead of wrapping when a value no longer fits
+pub trait WorryBackend: Sync {
+    type Level: Clone + fmt::Debug + fmt::Display + Eq + Hash + Send + Sync;
+
+    fn level(&self, worry: u64) -> Self::Level;
+    fn add(&self, a: &Self::Level, b: &Self::Level) -> Option<Self::Level>;
+    fn subtract(&self, a: &Self::Level, b: &Self::Level) -> Option<Self::Level>;
+    fn multiply(&self, a: &Self::Level, b: &Self::Level) -> Option<Self::Level>;
+    fn divide(&self, a: &Self::Level, b: &Self::Level) -> Option<Self::Level>;
+    fn relieve(&self, level: &Self::Level, divisor: u64) -> Self::Level;
+    fn is_divisible_by(&self, level: &Self::Level, divisor: u64) -> bool;
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum WorryMode {
+    // exact values, only practical for short runs since they grow without bound
+    Exact,
+    // values reduced modulo the LCM of the monkeys' divisors
+    Modular,
+}
+
+pub struct Exact;
+
+impl WorryBackend for Exact {
+    type Level = BigUint;
+
+    fn level(&self, worry: u64) -> Self::Level {
+        BigUint::from(worry)
+    }
+
+    fn add(&self, a: &Self::Level, b: &Self::Level) -> Option<Self::Level> {
+        Some(a + b)
+    }
+
+    fn subtract(&self, a: &Self::Level, b: &Self::Level) -> Option<Self::Level> {
+        (a >= b).then(|| a - b)
+    }
+
+    fn multiply(&self, a: &Self::Level, b: &Self::Level) -> Option<Self::Level> {
+        Some(a * b)
+    }
+
+    fn divide(&self, a: &Self::Level, b: &Self::Level) -> Option<Self::Level> {
+        (!b.is_zero()).then(|| a / b)
+    }
+
+    fn relieve(&self, level: &Self::Level, divisor: u64) -> Self::Level {
+        level / divisor
+    }
+
+    fn is_divisible_by(&self, level: &Self::Level, divisor: u64) -> bool {
+        (level % divisor).is_zero()
+    }
+}
+
+// the trick here is that every test is a divisibility test, so reducing the worry number modulo
+// the least common multiple of all the monkeys' test numbers doesn't change any of the outcomes
+//
+// e.g.
+// divisor tests: 3, 5, 7
+// item worry level: 123645
+// LCM of 3, 5, 7 = 105
+// 123645 % 3 = 0, 123645 % 5 = 0, 123645 % 7 = 4
+// 123645 % 105 = 60
+// 60 % 3 = 0, 60 % 5 = 0, 60 % 7 = 4
+//
+// this only holds while the relief divisor is 1: dividing after reducing gives a different
+// answer than dividing the real value, so with relief the values are kept as they are and
+// any overflow is reported instead
+pub struct Modular {
+    modulus: Option<u64>,
+}
+
+impl Modular {
+    pub fn new(divisors: impl Iterator<Item = u64>, relief_divisor: u64) -> Self {
+        let modulus = match relief_divisor {
+            1 => Some(divisors.fold(1, |acc, divisor| acc.lcm(&divisor))),
+            _ => None,
+        };
+        Modular { modulus }
+    }
+
+    fn reduce(&self, level: u64) -> u64 {
+        match self.modulus {
+            Some(modulus) => level % modulus,
+            None => level,
+        }
+    }
+}
+
+impl WorryBackend for Modular {
+    type Level = u64;
+
+    fn level(&self, worry: u64) -> Self::Level {
+        self.reduce(worry)
+    }
+
+    fn add(&self, a: &Self::Level, b: &Self::Level) -> Option<Self::Level> {
+        a.checked_add(*b).map(|level| self.reduce(level))
+    }
+
+    fn subtract(&self, a: &Self::Level, b: &Self::Level) -> Option<Self::Level> {
+        match self.modulus {
+            // the real value is unknown, so there's no telling whether it went negative
+            Some(_) => None,
+            None => a.checked_sub(*b),
+        }
+    }
+
+    fn multiply(&self, a: &Self::Level, b: &Self::Level) -> Option<Self::Level> {
+        a.checked_mul(*b).map(|level| self.reduce(level))
+    }
+
+    // a reduced value can't be divided, callers are expected to check for that up front
+    fn divide(&self, a: &Self::Level, b: &Self::Level) -> Option<Self::Level> {
+        match self.modulus {
+            Some(_) => None,
+            None => a.checked_div(*b),
+        }
+    }
+
+    fn relieve(&self, level: &Self::Level, divisor: u64) -> Self::Level {
+        level / divisor
+    }
+
+    fn is_divisible_by(&self, level: &Self::Level, divisor: u64) -> bool {
+        level % divisor == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_modular_reduces_by_lcm() {
+        let backend = Modular::new([4, 6].into_iter(), 1);
+        assert_eq!(backend.level(25), 1);
+        assert_eq!(backend.multiply(&11, &11), Some(1));
+    }
+
+    #[test]
+    fn test_modular_with_relief_keeps_values() {
+        let backend = Modular::new([4, 6].into_iter(), 3);
+        assert_eq!(backend.level(25), 25);
+        assert_eq!(backend.relieve(&25, 3), 8);
+    }
+
+    #[test]
+    fn test_modular_reports_overflow() {
+        let backend = Modular::new([4, 6].into_iter(), 3);
+        assert_eq!(backend.multiply(&u64::MAX, &2), None);
+        assert_eq!(backend.add(&u64::MAX, &1), None);
+    }
+
+    #[test]
+    fn test_modular_subtract() {
+        let backend = Modular::new([4, 6].into_iter(), 1);
+        assert_eq!(backend.subtract(&5, &2), None);
+        let backend = Modular::new([4, 6].into_iter(), 3);
+        assert_eq!(backend.subtract(&5, &2), Some(3));
+        assert_eq!(backend.subtract(&2, &5), None);
+    }
+
+    #[test]
+    fn test_exact_subtract_and_divide() {
+        let backend = Exact;
+        assert_eq!(backend.subtract(&backend.level(2), &backend.level(5)), None);
+        assert_eq!(backend.divide(&backend.level(7), &backend.level(0)), None);
+        assert_eq!(
+            backend.divide(&backend.level(7), &backend.level(2)),
+            Some(backend.level(3))
+        );
+    }
+
+    #[test]
+    fn test_exact_does_not_overflow() {
+        let backend = Exact;
+        let level = backend
+            .multiply(&backend.level(u64::MAX), &backend.level(2))
+            .unwrap();
+        assert_eq!(level, BigUint::from(u64::MAX) * 2u32);
+        assert!(backend.is_divisible_by(&level, 2));
+    }
+}
@@ -1,10 +1,10 @@
-use std::error::Error;
+use std::{error::Error, fmt};
 
 use nom::{
     bytes::complete::tag,
     character::complete::{digit1, multispace0},
     combinator::map_res,
-    multi::{many0, separated_list1},
+    multi::separated_list1,
     sequence::separated_pair,
     IResult,
 };
@@ -15,11 +15,80 @@ trait Parseable {
     where
         Self: Sized;
 }
-fn parse_cave_polygons(input: &str) -> Result<Vec<Polygon>, nom::Err<nom::error::Error<&str>>> {
-    let (_, polygons) = many0(Polygon::parse)(input)?;
+#[derive(Debug, PartialEq)]
+enum ParseCaveError {
+    Empty,
+    // line and column are 1-based so they match what an editor shows
+    Syntax { line: usize, column: usize },
+    Diagonal { line: usize, from: Point, to: Point },
+}
+
+impl fmt::Display for ParseCaveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseCaveError::Empty => write!(f, "no rock paths in input"),
+            ParseCaveError::Syntax { line, column } => {
+                write!(f, "unexpected input at line {}, column {}", line, column)
+            }
+            ParseCaveError::Diagonal { line, from, to } => write!(
+                f,
+                "diagonal segment {},{} -> {},{} at line {}",
+                from.x, from.y, to.x, to.y, line
+            ),
+        }
+    }
+}
+
+impl Error for ParseCaveError {}
+
+fn parse_cave_polygons(input: &str) -> Result<Vec<Polygon>, ParseCaveError> {
+    let mut polygons = vec![];
+    for (i, line) in input.lines().enumerate() {
+        let line_number = i + 1;
+        let line = line.trim_end();
+        if line.is_empty() {
+            continue;
+        }
+        let rest = match Polygon::parse(line) {
+            Ok(("", polygon)) => {
+                validate_polygon(&polygon, line_number)?;
+                polygons.push(polygon);
+                continue;
+            }
+            // a dangling arrow means the next point is malformed, so point at that instead
+            Ok((rest, _)) => match rest.strip_prefix(" -> ").map(Point::parse) {
+                Some(Err(nom::Err::Error(e) | nom::Err::Failure(e))) => e.input,
+                _ => rest,
+            },
+            Err(nom::Err::Error(e) | nom::Err::Failure(e)) => e.input,
+            Err(nom::Err::Incomplete(_)) => "",
+        };
+        return Err(ParseCaveError::Syntax {
+            line: line_number,
+            column: line.len() - rest.len() + 1,
+        });
+    }
+    if polygons.is_empty() {
+        return Err(ParseCaveError::Empty);
+    }
     Ok(polygons)
 }
 
+// the cave is drawn with straight lines only, so every segment has to be horizontal or vertical
+fn validate_polygon(polygon: &Polygon, line: usize) -> Result<(), ParseCaveError> {
+    for segment in polygon.points.windows(2) {
+        let (from, to) = (&segment[0], &segment[1]);
+        if from.x != to.x && from.y != to.y {
+            return Err(ParseCaveError::Diagonal {
+                line,
+                from: from.clone(),
+                to: to.clone(),
+            });
+        }
+    }
+    Ok(())
+}
+
 fn get_cave_bounds(polygons: &Vec<Polygon>) -> (usize, usize, usize) {
     polygons.iter().fold(
         (SAND_ENTRY_X, SAND_ENTRY_X, 0),
@@ -259,6 +328,40 @@ pub fn part2(input: &str) -> usize {
     let cave = fall_sand(input, true);
     count_sand(&cave)
 }
+
+// with the floor in place every grain comes to rest inside the triangle below the source,
+// and a cell ends up filled exactly when it isn't rock and one of the three cells above it is filled
+// so we can sweep the triangle row by row instead of dropping the grains one at a time
+pub fn part2_analytic(input: &str) -> usize {
+    let polygons = parse_cave_polygons(input).unwrap();
+    let (min, _, height) = get_cave_bounds(&polygons);
+    let cave = parse_cave(&polygons);
+    let is_rock = |x: usize, y: usize| -> bool {
+        x >= min && x - min < cave[y].len() && cave[y][x - min] == '#'
+    };
+
+    // the floor sits two rows below the lowest rock, sand rests on the row above it
+    let floor = height + 2;
+    let left = SAND_ENTRY_X - floor;
+    let width = 2 * floor + 1;
+
+    let mut row = vec![false; width];
+    row[SAND_ENTRY_X - left] = true;
+    let mut total = 1;
+    for y in 1..floor {
+        let next_row: Vec<bool> = (0..width)
+            .map(|i| {
+                // rows below the rock map are open all the way down to the floor
+                let rock = y < cave.len() && is_rock(left + i, y);
+                let above = i.saturating_sub(1)..=std::cmp::min(i + 1, width - 1);
+                !rock && row[above].iter().any(|filled| *filled)
+            })
+            .collect();
+        total += next_row.iter().filter(|filled| **filled).count();
+        row = next_row;
+    }
+    total
+}
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -308,6 +411,51 @@ mod tests {
         assert_eq!(actual, expected);
     }
     #[test]
+    fn test_parse_cave_polygons_empty() {
+        assert_eq!(parse_cave_polygons(""), Err(ParseCaveError::Empty));
+        assert_eq!(parse_cave_polygons("\n  \n"), Err(ParseCaveError::Empty));
+    }
+    #[test]
+    fn test_parse_cave_polygons_diagonal() {
+        const INPUT: &str = r#"498,4 -> 498,6 -> 496,6
+503,4 -> 502,5 -> 502,9"#;
+        let expected = Err(ParseCaveError::Diagonal {
+            line: 2,
+            from: Point { x: 503, y: 4 },
+            to: Point { x: 502, y: 5 },
+        });
+        assert_eq!(parse_cave_polygons(INPUT), expected);
+    }
+    #[test]
+    fn test_parse_cave_polygons_trailing_garbage() {
+        const INPUT: &str = r#"498,4 -> 498,6 -> 496,6
+503,4 -> 502,4 -> 502,9 -> 494,9 oops"#;
+        let expected = Err(ParseCaveError::Syntax {
+            line: 2,
+            column: 33,
+        });
+        assert_eq!(parse_cave_polygons(INPUT), expected);
+    }
+    #[test]
+    fn test_parse_cave_polygons_malformed_segment() {
+        // previously this stopped quietly after the first path
+        const INPUT: &str = r#"498,4 -> 498,6 -> 496,6
+503,4 -> 502,x -> 502,9"#;
+        let expected = Err(ParseCaveError::Syntax {
+            line: 2,
+            column: 14,
+        });
+        assert_eq!(parse_cave_polygons(INPUT), expected);
+    }
+    #[test]
+    fn test_parse_cave_error_display() {
+        let error = parse_cave_polygons("498,4 -> 497,5").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "diagonal segment 498,4 -> 497,5 at line 1"
+        );
+    }
+    #[test]
     fn test_fall_sand() {
         const INPUT: &str = r#"498,4 -> 498,6 -> 496,6
 503,4 -> 502,4 -> 502,9 -> 494,9"#;
@@ -369,4 +517,22 @@ mod tests {
         let actual = count_sand(&cave);
         assert_eq!(expected, actual);
     }
+    #[test]
+    fn test_count_sand_analytic() {
+        const INPUT: &str = r#"498,4 -> 498,6 -> 496,6
+503,4 -> 502,4 -> 502,9 -> 494,9"#;
+        let expected = count_sand(&fall_sand(INPUT, true));
+        let actual = part2_analytic(INPUT);
+        assert_eq!(expected, actual);
+        assert_eq!(93, actual);
+    }
+    #[test]
+    fn test_count_sand_analytic_shadowed() {
+        // a wide shelf right under the source leaves a hollow beneath it that the sand can't reach
+        const INPUT: &str = r#"495,2 -> 505,2
+490,5 -> 490,7 -> 510,7 -> 510,5"#;
+        let expected = count_sand(&fall_sand(INPUT, true));
+        let actual = part2_analytic(INPUT);
+        assert_eq!(expected, actual);
+    }
 }
@@ -0,0 +1,79 @@
+use std::ops::BitAnd;
+
+use super::get_item_priority;
+
+// a set of items with one bit per priority, a-z are bits 1-26 and A-Z bits 27-52
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct ItemSet(u64);
+
+impl ItemSet {
+    // every item there is, so intersecting with it changes nothing
+    pub const ALL: ItemSet = ItemSet(((1 << 52) - 1) << 1);
+
+    // the first item that isn't a letter when there is one
+    pub fn from_items(items: &str) -> Result<ItemSet, char> {
+        items.chars().try_fold(ItemSet::default(), |set, item| {
+            if item.is_ascii_alphabetic() {
+                Ok(ItemSet(set.0 | 1 << get_item_priority(item)))
+            } else {
+                Err(item)
+            }
+        })
+    }
+
+    pub fn len(&self) -> u32 {
+        self.0.count_ones()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    // in order of priority
+    pub fn items(&self) -> Vec<char> {
+        ('a'..='z')
+            .chain('A'..='Z')
+            .filter(|&item| self.0 & 1 << get_item_priority(item) != 0)
+            .collect()
+    }
+
+    pub fn priority_sum(&self) -> u32 {
+        (1..=52)
+            .filter(|priority| self.0 & 1 << priority != 0)
+            .sum()
+    }
+}
+
+impl BitAnd for ItemSet {
+    type Output = ItemSet;
+
+    fn bitand(self, other: ItemSet) -> ItemSet {
+        ItemSet(self.0 & other.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_items() {
+        let set = ItemSet::from_items("aZzab").unwrap();
+        assert_eq!(set.len(), 4);
+        assert_eq!(set.items(), vec!['a', 'b', 'z', 'Z']);
+        assert_eq!(set.priority_sum(), 1 + 2 + 26 + 52);
+        assert_eq!(ItemSet::from_items("ab1c"), Err('1'));
+    }
+
+    #[test]
+    fn test_intersection() {
+        let a = ItemSet::from_items("vJrwpWtwJgWrhcsFMMfFFhFp").unwrap();
+        let b = ItemSet::from_items("jqHRNqRjqzjGDLGLrsFMfFZSrLrFZsSL").unwrap();
+        let c = ItemSet::from_items("PmmdzqPrVvPwwTWBwg").unwrap();
+        assert_eq!((a & b & c).items(), vec!['r']);
+        assert_eq!(ItemSet::ALL & a, a);
+        assert!(
+            (ItemSet::from_items("ab").unwrap() & ItemSet::from_items("AB").unwrap()).is_empty()
+        );
+    }
+}
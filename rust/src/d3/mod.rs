@@ -1,21 +1,112 @@
-use std::collections::HashSet;
-
-fn get_shared_items_in_rucksack_compartments(input: &str) -> Vec<char> {
-    // split input 50/50
-    let num_items_per_compartment = input.len() / 2;
-    let compartment1 = input[..num_items_per_compartment]
-        .chars()
-        .into_iter()
-        .collect::<HashSet<char>>();
-    let compartment2 = input[num_items_per_compartment..]
-        .chars()
-        .into_iter()
-        .collect::<HashSet<char>>();
-
-    compartment1
-        .into_iter()
-        .filter(|e| compartment2.contains(e))
-        .collect()
+use std::{error::Error, fmt};
+
+mod items;
+
+pub use items::ItemSet;
+
+#[derive(Debug, Clone, Copy)]
+pub struct RucksackConfig {
+    // each rucksack is split into this many equally sized compartments
+    pub num_compartments: usize,
+    // this many elves in a row make up a group sharing one badge
+    pub group_size: usize,
+}
+
+impl Default for RucksackConfig {
+    fn default() -> Self {
+        RucksackConfig {
+            num_compartments: 2,
+            group_size: 3,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum RucksackError {
+    // lines and groups are numbered from 1
+    InvalidItem {
+        line: usize,
+        item: char,
+    },
+    UnevenCompartments {
+        line: usize,
+        num_items: usize,
+        num_compartments: usize,
+    },
+    ZeroGroupSize,
+    IncompleteGroup {
+        group: usize,
+        num_elves: usize,
+        group_size: usize,
+    },
+    NoBadge {
+        group: usize,
+    },
+    MultipleBadges {
+        group: usize,
+        badges: Vec<char>,
+    },
+}
+
+impl fmt::Display for RucksackError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RucksackError::InvalidItem { line, item } => {
+                write!(f, "invalid item {:?} at line {}", item, line)
+            }
+            RucksackError::UnevenCompartments {
+                line,
+                num_items,
+                num_compartments,
+            } => write!(
+                f,
+                "{} items at line {} can't be split into {} compartments",
+                num_items, line, num_compartments
+            ),
+            RucksackError::ZeroGroupSize => write!(f, "a group needs at least one elf"),
+            RucksackError::IncompleteGroup {
+                group,
+                num_elves,
+                group_size,
+            } => write!(
+                f,
+                "group {} only has {} of {} elves",
+                group, num_elves, group_size
+            ),
+            RucksackError::NoBadge { group } => write!(f, "group {} has no badge", group),
+            RucksackError::MultipleBadges { group, badges } => {
+                write!(f, "group {} has more than one badge: {:?}", group, badges)
+            }
+        }
+    }
+}
+
+impl Error for RucksackError {}
+
+fn get_item_set(items: &str, line: usize) -> Result<ItemSet, RucksackError> {
+    ItemSet::from_items(items).map_err(|item| RucksackError::InvalidItem { line, item })
+}
+
+fn get_shared_items_in_rucksack_compartments(
+    input: &str,
+    num_compartments: usize,
+    line: usize,
+) -> Result<ItemSet, RucksackError> {
+    if num_compartments == 0 || !input.len().is_multiple_of(num_compartments) {
+        return Err(RucksackError::UnevenCompartments {
+            line,
+            num_items: input.len(),
+            num_compartments,
+        });
+    }
+    // items are ASCII letters, so splitting by bytes is safe once they've been checked
+    get_item_set(input, line)?;
+    let num_items_per_compartment = input.len() / num_compartments;
+    (0..num_compartments).try_fold(ItemSet::ALL, |shared, i| {
+        let compartment =
+            &input[i * num_items_per_compartment..(i + 1) * num_items_per_compartment];
+        Ok(shared & get_item_set(compartment, line)?)
+    })
 }
 
 fn get_item_priority(input: char) -> u32 {
@@ -30,62 +121,73 @@ fn get_item_priority(input: char) -> u32 {
     }
 }
 
-pub fn get_priority_sum(input: &str) -> u32 {
-    let shared_items: Vec<Vec<char>> = input
-        .split("\n")
-        .map(|x| get_shared_items_in_rucksack_compartments(x))
-        .collect();
-    let priorities: Vec<Vec<u32>> = shared_items
-        .into_iter()
-        .map(|x| x.into_iter().map(get_item_priority).collect())
-        .collect();
-
-    priorities
-        .into_iter()
-        .flatten()
-        .collect::<Vec<u32>>()
-        .iter()
-        .sum()
+pub fn priority_sum(input: &str, config: &RucksackConfig) -> Result<u32, RucksackError> {
+    let mut sum = 0;
+    for (i, rucksack) in input.lines().enumerate() {
+        let shared_items =
+            get_shared_items_in_rucksack_compartments(rucksack, config.num_compartments, i + 1)?;
+        sum += shared_items.priority_sum();
+    }
+    Ok(sum)
 }
 
-pub fn get_group_badge(input: &str) -> char {
-    let mut unique_items_per_elf = input
-        .split("\n")
-        .collect::<Vec<&str>>()
-        .iter()
-        .map(|x| x.chars().into_iter().collect::<HashSet<char>>())
-        .collect::<Vec<HashSet<char>>>();
-
-    let mut result = unique_items_per_elf.pop().unwrap();
-    result.retain(|item| unique_items_per_elf.iter().all(|set| set.contains(item)));
-    assert_eq!(result.len(), 1);
-    result.into_iter().collect::<Vec<char>>()[0]
+pub fn get_priority_sum(input: &str) -> Result<u32, RucksackError> {
+    priority_sum(input, &RucksackConfig::default())
 }
 
-fn get_groups(input: &str) -> Vec<String> {
-    let lines = input.split("\n").collect::<Vec<&str>>();
-    assert_eq!(lines.len() % 3, 0);
+// `first_line` is where the group starts in the whole list, so errors point at the right line
+pub fn get_group_badge(
+    input: &str,
+    group: usize,
+    first_line: usize,
+) -> Result<char, RucksackError> {
+    let mut shared_items = ItemSet::ALL;
+    for (i, rucksack) in input.lines().enumerate() {
+        shared_items = shared_items & get_item_set(rucksack, first_line + i)?;
+    }
+    if shared_items.is_empty() {
+        Err(RucksackError::NoBadge { group })
+    } else if shared_items.len() > 1 {
+        Err(RucksackError::MultipleBadges {
+            group,
+            badges: shared_items.items(),
+        })
+    } else {
+        Ok(shared_items.items()[0])
+    }
+}
+
+fn get_groups(input: &str, group_size: usize) -> Result<Vec<String>, RucksackError> {
+    if group_size == 0 {
+        return Err(RucksackError::ZeroGroupSize);
+    }
+    let lines = input.lines().collect::<Vec<&str>>();
     let mut result: Vec<String> = vec![];
-    let mut i = 0;
-    while i < lines.len() - 1 {
-        result.push(lines[i..i + 3].join("\n"));
-        i += 3;
+    for (i, group) in lines.chunks(group_size).enumerate() {
+        if group.len() != group_size {
+            return Err(RucksackError::IncompleteGroup {
+                group: i + 1,
+                num_elves: group.len(),
+                group_size,
+            });
+        }
+        result.push(group.join("\n"));
+    }
+    Ok(result)
+}
+
+pub fn badge_priority_total(input: &str, config: &RucksackConfig) -> Result<u32, RucksackError> {
+    let groups = get_groups(input, config.group_size)?;
+    let mut total = 0;
+    for (i, group) in groups.iter().enumerate() {
+        let first_line = i * config.group_size + 1;
+        total += get_item_priority(get_group_badge(group, i + 1, first_line)?);
     }
-    result
+    Ok(total)
 }
 
-pub fn get_badge_priority_total(input: &str) -> u32 {
-    let groups = get_groups(input);
-    let badges: Vec<char> = groups
-        .into_iter()
-        .map(|x| get_group_badge(x.as_str()))
-        .collect();
-    badges
-        .into_iter()
-        .map(get_item_priority)
-        .collect::<Vec<u32>>()
-        .iter()
-        .sum()
+pub fn get_badge_priority_total(input: &str) -> Result<u32, RucksackError> {
+    badge_priority_total(input, &RucksackConfig::default())
 }
 
 #[cfg(test)]
@@ -105,7 +207,9 @@ mod tests {
         #[case] input: &str,
         #[case] expected: Vec<char>,
     ) {
-        let actual = get_shared_items_in_rucksack_compartments(input);
+        let actual = get_shared_items_in_rucksack_compartments(input, 2, 1)
+            .unwrap()
+            .items();
         println!("{:?}", actual);
         assert_eq!(actual, expected);
     }
@@ -142,7 +246,7 @@ CrZsJsPPZsGzwwsLwLmpwMDw"#;
 
         let actual = get_priority_sum(input);
         let expected = 157;
-        assert_eq!(actual, expected);
+        assert_eq!(actual, Ok(expected));
     }
 
     #[test]
@@ -151,15 +255,15 @@ CrZsJsPPZsGzwwsLwLmpwMDw"#;
 jqHRNqRjqzjGDLGLrsFMfFZSrLrFZsSL
 PmmdzqPrVvPwwTWBwg"#;
         let expected = 'r';
-        let actual = get_group_badge(input);
-        assert_eq!(actual, expected);
+        let actual = get_group_badge(input, 1, 1);
+        assert_eq!(actual, Ok(expected));
 
         let input = r#"wMqvLMZHhHMvwLHjbvcjnnSBnvTQFn
 ttgJtRGJQctTZtZT
 CrZsJsPPZsGzwwsLwLmpwMDw"#;
         let expected = 'Z';
-        let actual = get_group_badge(input);
-        assert_eq!(actual, expected);
+        let actual = get_group_badge(input, 1, 1);
+        assert_eq!(actual, Ok(expected));
     }
 
     #[test]
@@ -174,7 +278,7 @@ CrZsJsPPZsGzwwsLwLmpwMDw"#;
             "vJrwpWtwJgWrhcsFMMfFFhFp\njqHRNqRjqzjGDLGLrsFMfFZSrLrFZsSL\nPmmdzqPrVvPwwTWBwg",
             "wMqvLMZHhHMvwLHjbvcjnnSBnvTQFn\nttgJtRGJQctTZtZT\nCrZsJsPPZsGzwwsLwLmpwMDw",
         ];
-        let actual = get_groups(input);
+        let actual = get_groups(input, 3).unwrap();
         assert_eq!(actual, expected);
     }
 
@@ -189,6 +293,122 @@ CrZsJsPPZsGzwwsLwLmpwMDw"#;
 
         let expected = 70;
         let actual = get_badge_priority_total(input);
-        assert_eq!(actual, expected);
+        assert_eq!(actual, Ok(expected));
+    }
+
+    #[rstest]
+    #[case("abbaab", 3, vec!['a', 'b'])]
+    #[case("abcaXbaYZ", 3, vec!['a'])]
+    #[case("abcdef", 1, vec!['a', 'b', 'c', 'd', 'e', 'f'])]
+    #[case("abcdef", 6, vec![])]
+    fn test_shared_items_in_more_compartments(
+        #[case] input: &str,
+        #[case] num_compartments: usize,
+        #[case] expected: Vec<char>,
+    ) {
+        let actual = get_shared_items_in_rucksack_compartments(input, num_compartments, 1);
+        assert_eq!(actual.unwrap().items(), expected);
+    }
+
+    #[test]
+    fn test_configured_group_size() {
+        let input = r#"vJrwpWtwJgWrhcsFMMfFFhFp
+jqHRNqRjqzjGDLGLrsFMfFZSrLrFZsSL
+PmmdzqPrVvPwwTWBwg
+wMqvLMZHhHMvwLHjbvcjnnSBnvTQFn
+ttgJtRGJQctTZtZT
+CrZsJsPPZsGzwwsLwLmpwMDw"#;
+        let config = RucksackConfig {
+            group_size: 6,
+            ..Default::default()
+        };
+
+        // r and Z are only shared within their own group of three
+        assert_eq!(
+            badge_priority_total(input, &config),
+            Err(RucksackError::NoBadge { group: 1 })
+        );
+
+        let config = RucksackConfig {
+            group_size: 2,
+            ..Default::default()
+        };
+        assert_eq!(
+            badge_priority_total(input, &config),
+            Err(RucksackError::MultipleBadges {
+                group: 1,
+                badges: vec!['f', 'r', 's', 'F', 'M']
+            })
+        );
+    }
+
+    #[rstest]
+    #[case("vJrwpWtwJgWrhcsFMMfFFhFpx", 2, RucksackError::UnevenCompartments { line: 1, num_items: 25, num_compartments: 2 })]
+    #[case("abcaXb", 4, RucksackError::UnevenCompartments { line: 1, num_items: 6, num_compartments: 4 })]
+    #[case("abca\nab-b", 2, RucksackError::InvalidItem { line: 2, item: '-' })]
+    fn test_priority_sum_errors(
+        #[case] input: &str,
+        #[case] num_compartments: usize,
+        #[case] expected: RucksackError,
+    ) {
+        let config = RucksackConfig {
+            num_compartments,
+            ..Default::default()
+        };
+        assert_eq!(priority_sum(input, &config), Err(expected));
+    }
+
+    #[test]
+    fn test_zero_group_size() {
+        let config = RucksackConfig {
+            group_size: 0,
+            ..Default::default()
+        };
+        let actual = badge_priority_total("vJrwpWtwJgWrhcsFMMfFFhFp", &config);
+        assert_eq!(actual, Err(RucksackError::ZeroGroupSize));
+        assert_eq!(
+            actual.unwrap_err().to_string(),
+            "a group needs at least one elf"
+        );
+    }
+
+    #[test]
+    fn test_incomplete_group() {
+        let input = r#"vJrwpWtwJgWrhcsFMMfFFhFp
+jqHRNqRjqzjGDLGLrsFMfFZSrLrFZsSL
+PmmdzqPrVvPwwTWBwg
+wMqvLMZHhHMvwLHjbvcjnnSBnvTQFn"#;
+        let actual = get_badge_priority_total(input);
+        assert_eq!(
+            actual,
+            Err(RucksackError::IncompleteGroup {
+                group: 2,
+                num_elves: 1,
+                group_size: 3
+            })
+        );
+        assert_eq!(
+            actual.unwrap_err().to_string(),
+            "group 2 only has 1 of 3 elves"
+        );
+    }
+
+    #[test]
+    fn test_invalid_item_in_second_group() {
+        let input = r#"vJrwpWtwJgWrhcsFMMfFFhFp
+jqHRNqRjqzjGDLGLrsFMfFZSrLrFZsSL
+PmmdzqPrVvPwwTWBwg
+wMqvLMZHhHMvwLHjbvcjnnSBnvTQFn
+ttgJtRGJQct?ZtZT
+CrZsJsPPZsGzwwsLwLmpwMDw"#;
+        let actual = get_badge_priority_total(input);
+        assert_eq!(
+            actual,
+            Err(RucksackError::InvalidItem { line: 5, item: '?' })
+        );
+        assert_eq!(
+            actual.unwrap_err().to_string(),
+            "invalid item '?' at line 5"
+        );
     }
 }
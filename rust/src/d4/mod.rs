@@ -1,26 +1,112 @@
+use std::{error::Error, fmt};
+
+use nom::{
+    character::complete::{char, u32},
+    sequence::separated_pair,
+    IResult,
+};
+
+mod range;
+
+pub use range::SectionRange;
+
+trait Parseable {
+    fn parse(input: &str) -> IResult<&str, Self>
+    where
+        Self: Sized;
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ParseAssignmentError {
+    // lines and columns are counted from 1
+    NotANumber {
+        line: usize,
+        column: usize,
+    },
+    MissingHalf {
+        line: usize,
+        column: usize,
+    },
+    // something other than a comma after a range, e.g. "2-4;6-8" or "2-4x"
+    BadSeparator {
+        line: usize,
+        column: usize,
+    },
+    Reversed {
+        line: usize,
+        column: usize,
+        range: (u32, u32),
+    },
+    GroupSize {
+        line: usize,
+        expected: usize,
+        found: usize,
+    },
+}
+
+impl fmt::Display for ParseAssignmentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseAssignmentError::NotANumber { line, column } => {
+                write!(f, "expected a number at line {}, column {}", line, column)
+            }
+            ParseAssignmentError::MissingHalf { line, column } => write!(
+                f,
+                "section range is missing a half at line {}, column {}",
+                line, column
+            ),
+            ParseAssignmentError::BadSeparator { line, column } => write!(
+                f,
+                "expected a comma between section ranges at line {}, column {}",
+                line, column
+            ),
+            ParseAssignmentError::Reversed {
+                line,
+                column,
+                range,
+            } => write!(
+                f,
+                "section range {}-{} is reversed at line {}, column {}",
+                range.0, range.1, line, column
+            ),
+            ParseAssignmentError::GroupSize {
+                line,
+                expected,
+                found,
+            } => write!(
+                f,
+                "expected {} section ranges at line {}, found {}",
+                expected, line, found
+            ),
+        }
+    }
+}
+
+impl Error for ParseAssignmentError {}
+
 #[derive(Debug, PartialEq)]
 struct Job {
     min_id: u32,
     max_id: u32,
 }
 
+impl Parseable for Job {
+    fn parse(input: &str) -> IResult<&str, Self> {
+        let (input, (min_id, max_id)) = separated_pair(u32, char('-'), u32)(input)?;
+        Ok((input, Job { min_id, max_id }))
+    }
+}
+
 impl Job {
-    pub fn from_string(id_range_string: &str) -> Job {
-        let job_id_range: Vec<u32> = id_range_string
-            .split("-")
-            .collect::<Vec<&str>>()
-            .into_iter()
-            .map(|x| x.parse().unwrap_or_default())
-            .collect::<Vec<u32>>();
-
-        Job {
-            min_id: job_id_range[0],
-            max_id: job_id_range[1],
+    fn range(&self) -> SectionRange {
+        SectionRange {
+            start: self.min_id,
+            end: self.max_id,
         }
     }
 
     pub fn is_fully_contained_in(&self, other: &Job) -> bool {
-        self.min_id >= other.min_id && self.max_id <= other.max_id
+        other.range().contains(&self.range())
     }
 }
 
@@ -31,11 +117,14 @@ struct PairAssignment {
 }
 
 impl PairAssignment {
-    pub fn from_string(input: &str) -> PairAssignment {
-        let job_id_ranges: Vec<&str> = input.split(",").collect::<Vec<&str>>();
-        PairAssignment {
-            job1: Job::from_string(job_id_ranges[0]),
-            job2: Job::from_string(job_id_ranges[1]),
+    pub fn from_string(input: &str, line: usize) -> Result<PairAssignment, ParseAssignmentError> {
+        match <[Job; 2]>::try_from(parse_group(input, line)?) {
+            Ok([job1, job2]) => Ok(PairAssignment { job1, job2 }),
+            Err(group) => Err(ParseAssignmentError::GroupSize {
+                line,
+                expected: 2,
+                found: group.len(),
+            }),
         }
     }
 
@@ -44,37 +133,202 @@ impl PairAssignment {
     }
 
     pub fn overlaps(&self) -> bool {
-        (self.job1.min_id >= self.job2.min_id && self.job1.min_id <= self.job2.max_id)
-            || (self.job1.max_id <= self.job2.max_id && self.job1.max_id >= self.job2.min_id)
-            || self.is_one_of_pair_fully_contained_in_the_other()
+        self.job1.range().intersection(&self.job2.range()).is_some()
+    }
+}
+
+// one or more comma separated ranges, e.g. "2-4,6-8" for a pair of elves or "2-4,6-8,3-5" for a
+// team of three
+fn parse_group(line: &str, line_number: usize) -> Result<Vec<Job>, ParseAssignmentError> {
+    let column = |rest: &str| line.len() - rest.len() + 1;
+    // anything that isn't the start of a number where one is expected means a half went missing,
+    // e.g. "2-", "2,4-5" or "-4"
+    let unexpected = |rest: &str| match rest.chars().next() {
+        None | Some('-') | Some(',') => ParseAssignmentError::MissingHalf {
+            line: line_number,
+            column: column(rest),
+        },
+        Some(_) => ParseAssignmentError::NotANumber {
+            line: line_number,
+            column: column(rest),
+        },
+    };
+
+    // a range at a time, so whatever follows a comma is checked as a range of its own
+    let mut jobs = vec![];
+    let mut rest = line;
+    loop {
+        let (after, job) = match Job::parse(rest) {
+            Ok(parsed) => parsed,
+            Err(nom::Err::Error(e) | nom::Err::Failure(e)) => return Err(unexpected(e.input)),
+            Err(nom::Err::Incomplete(_)) => return Err(unexpected("")),
+        };
+        if job.min_id > job.max_id {
+            return Err(ParseAssignmentError::Reversed {
+                line: line_number,
+                column: column(rest),
+                range: (job.min_id, job.max_id),
+            });
+        }
+        jobs.push(job);
+        rest = match after.strip_prefix(',') {
+            Some(next) => next,
+            None if after.is_empty() => return Ok(jobs),
+            None => {
+                return Err(ParseAssignmentError::BadSeparator {
+                    line: line_number,
+                    column: column(after),
+                })
+            }
+        };
     }
 }
 
-fn get_pair_assignments_from_input(input: &str) -> Vec<PairAssignment> {
+fn get_groups_from_input(input: &str) -> Result<Vec<Vec<Job>>, ParseAssignmentError> {
     input
-        .split("\n")
-        .collect::<Vec<&str>>()
-        .iter()
-        .map(|x| PairAssignment::from_string(x))
-        .collect::<Vec<PairAssignment>>()
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim_end().is_empty())
+        .map(|(i, line)| parse_group(line.trim_end(), i + 1))
+        .collect()
+}
+
+fn get_pair_assignments_from_input(
+    input: &str,
+) -> Result<Vec<PairAssignment>, ParseAssignmentError> {
+    input
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim_end().is_empty())
+        .map(|(i, line)| PairAssignment::from_string(line.trim_end(), i + 1))
+        .collect()
 }
 
-pub fn get_total_number_of_fully_contained_assignment_pairs(input: &str) -> u32 {
-    let pair_assignments = get_pair_assignments_from_input(input);
-    pair_assignments
+pub fn get_total_number_of_fully_contained_assignment_pairs(
+    input: &str,
+) -> Result<u32, ParseAssignmentError> {
+    let pair_assignments = get_pair_assignments_from_input(input)?;
+    Ok(pair_assignments
         .into_iter()
         .filter(|pair| pair.is_one_of_pair_fully_contained_in_the_other())
         .collect::<Vec<PairAssignment>>()
-        .len() as u32
+        .len() as u32)
 }
 
-pub fn get_total_number_of_overlapping_assignment_pairs(input: &str) -> u32 {
-    let pair_assignments = get_pair_assignments_from_input(input);
-    pair_assignments
+pub fn get_total_number_of_overlapping_assignment_pairs(
+    input: &str,
+) -> Result<u32, ParseAssignmentError> {
+    let pair_assignments = get_pair_assignments_from_input(input)?;
+    Ok(pair_assignments
         .into_iter()
         .filter(|pair| pair.overlaps())
         .collect::<Vec<PairAssignment>>()
-        .len() as u32
+        .len() as u32)
+}
+
+// every elf's sections in input order, whatever the size of the groups on each line
+fn get_section_ranges_from_input(input: &str) -> Result<Vec<SectionRange>, ParseAssignmentError> {
+    Ok(get_groups_from_input(input)?
+        .iter()
+        .flatten()
+        .map(Job::range)
+        .collect())
+}
+
+// the gaps between the lowest and highest section anyone has been assigned
+pub fn uncovered_sections(ranges: &[SectionRange]) -> Vec<SectionRange> {
+    let merged = SectionRange::merge(ranges);
+    let (Some(first), Some(last)) = (merged.first(), merged.last()) else {
+        return vec![];
+    };
+    let mut uncovered = vec![SectionRange {
+        start: first.start,
+        end: last.end,
+    }];
+    for range in &merged {
+        uncovered = uncovered
+            .iter()
+            .flat_map(|gap| gap.difference(range))
+            .collect();
+    }
+    uncovered
+}
+
+// the first stretch of sections shared by as many elves as possible, along with how many that is
+pub fn most_overlapped_sections(ranges: &[SectionRange]) -> Option<(SectionRange, usize)> {
+    // +1 where a range starts and -1 just after it ends, sorted so ends come before starts
+    let mut events: Vec<(u64, i64)> = ranges
+        .iter()
+        .flat_map(|range| [(range.start as u64, 1), (range.end as u64 + 1, -1)])
+        .collect();
+    events.sort_by_key(|&(section, change)| (section, change));
+
+    let mut best: Option<(SectionRange, usize)> = None;
+    let mut count = 0;
+    for (i, &(section, change)) in events.iter().enumerate() {
+        count += change;
+        let Some(&(next, _)) = events.get(i + 1) else {
+            break;
+        };
+        if next == section || count <= 0 {
+            continue;
+        }
+        let range = SectionRange {
+            start: section as u32,
+            end: (next - 1) as u32,
+        };
+        best = match best {
+            // one range ending right where another starts doesn't break up the stretch
+            Some((stretch, most)) if most == count as usize && stretch.union(&range).is_some() => {
+                Some((stretch.union(&range).unwrap(), most))
+            }
+            Some((_, most)) if most >= count as usize => best,
+            _ => Some((range, count as usize)),
+        };
+    }
+    best
+}
+
+// elves whose sections overlap, directly or through other elves in the same group, as indices into
+// the list of ranges; groups are ordered by their lowest section and elves within them by index
+pub fn overlapping_groups(ranges: &[SectionRange]) -> Vec<Vec<usize>> {
+    let mut order: Vec<usize> = (0..ranges.len()).collect();
+    order.sort_by_key(|&i| ranges[i]);
+
+    let mut groups: Vec<Vec<usize>> = vec![];
+    let mut group_end = 0;
+    for i in order {
+        match groups.last_mut() {
+            Some(group) if ranges[i].start <= group_end => {
+                group.push(i);
+                group_end = group_end.max(ranges[i].end);
+            }
+            _ => {
+                groups.push(vec![i]);
+                group_end = ranges[i].end;
+            }
+        }
+    }
+    for group in groups.iter_mut() {
+        group.sort();
+    }
+    groups
+}
+
+pub fn get_uncovered_sections(input: &str) -> Result<Vec<SectionRange>, ParseAssignmentError> {
+    Ok(uncovered_sections(&get_section_ranges_from_input(input)?))
+}
+
+pub fn get_most_overlapped_sections(
+    input: &str,
+) -> Result<Option<(SectionRange, usize)>, ParseAssignmentError> {
+    Ok(most_overlapped_sections(&get_section_ranges_from_input(
+        input,
+    )?))
+}
+
+pub fn get_overlapping_groups(input: &str) -> Result<Vec<Vec<usize>>, ParseAssignmentError> {
+    Ok(overlapping_groups(&get_section_ranges_from_input(input)?))
 }
 
 #[cfg(test)]
@@ -87,7 +341,7 @@ mod tests {
     #[case("1-2", Job{min_id: 1, max_id: 2})]
     #[case("1324-2563", Job{min_id: 1324, max_id: 2563})]
     fn test_job_from_string(#[case] input: &str, #[case] expected: Job) {
-        assert_eq!(Job::from_string(input), expected)
+        assert_eq!(Job::parse(input), Ok(("", expected)))
     }
 
     #[rstest]
@@ -110,8 +364,8 @@ mod tests {
                 max_id: 4,
             },
         };
-        let actual = PairAssignment::from_string(input);
-        assert_eq!(actual, expected);
+        let actual = PairAssignment::from_string(input, 1);
+        assert_eq!(actual, Ok(expected));
     }
 
     #[rstest]
@@ -140,7 +394,7 @@ mod tests {
     #[case("6-6,4-6", true)]
     #[case("2-6,4-8", true)]
     fn test_jobs_overlap(#[case] id_string: &str, #[case] expected: bool) {
-        let pa = PairAssignment::from_string(id_string);
+        let pa = PairAssignment::from_string(id_string, 1).unwrap();
         assert_eq!(
             pa.overlaps(),
             expected,
@@ -178,7 +432,7 @@ mod tests {
         ];
 
         let actual = get_pair_assignments_from_input(input);
-        assert_eq!(actual, expected);
+        assert_eq!(actual, Ok(expected));
     }
 
     #[test]
@@ -192,7 +446,7 @@ mod tests {
 
         let expected = 2;
         let actual = get_total_number_of_fully_contained_assignment_pairs(input);
-        assert_eq!(actual, expected);
+        assert_eq!(actual, Ok(expected));
     }
 
     #[test]
@@ -206,6 +460,125 @@ mod tests {
 
         let expected = 4;
         let actual = get_total_number_of_overlapping_assignment_pairs(input);
-        assert_eq!(actual, expected);
+        assert_eq!(actual, Ok(expected));
+    }
+
+    fn ranges(pairs: &[(u32, u32)]) -> Vec<SectionRange> {
+        pairs
+            .iter()
+            .map(|&(start, end)| SectionRange { start, end })
+            .collect()
+    }
+
+    #[test]
+    fn test_get_uncovered_sections() {
+        let input = r#"2-4,6-8
+2-3,4-5
+5-7,7-9
+12-13,15-15"#;
+
+        let expected = ranges(&[(10, 11), (14, 14)]);
+        assert_eq!(get_uncovered_sections(input), Ok(expected));
+    }
+
+    #[test]
+    fn test_get_most_overlapped_sections() {
+        let input = r#"2-4,6-8
+2-3,4-5
+5-7,7-9
+2-8,3-7
+6-6,4-6
+2-6,4-8"#;
+
+        let expected = Some((SectionRange { start: 6, end: 6 }, 8));
+        assert_eq!(get_most_overlapped_sections(input), Ok(expected));
+    }
+
+    #[rstest]
+    #[case(&[(1, 3), (3, 5)], Some(((3, 3), 2)))]
+    #[case(&[(1, 3), (4, 5)], Some(((1, 5), 1)))]
+    #[case(&[(1, 4), (1, 2), (3, 4)], Some(((1, 4), 2)))]
+    #[case(&[(1, 2), (1, 2), (4, 5), (4, 5)], Some(((1, 2), 2)))]
+    #[case(&[(1, 10), (2, 3), (5, 6), (5, 5)], Some(((5, 5), 3)))]
+    #[case(&[], None)]
+    fn test_most_overlapped_sections(
+        #[case] input: &[(u32, u32)],
+        #[case] expected: Option<((u32, u32), usize)>,
+    ) {
+        let expected = expected.map(|((start, end), count)| (SectionRange { start, end }, count));
+        assert_eq!(most_overlapped_sections(&ranges(input)), expected);
+    }
+
+    #[test]
+    fn test_get_overlapping_groups() {
+        // 5-7 and 7-9 link up with 2-8 and 3-7 on the last lines, 10-11 is on its own
+        let input = r#"2-4,6-8
+5-7,7-9
+10-11,12-12
+2-8,3-7"#;
+
+        let expected = vec![vec![0, 1, 2, 3, 6, 7], vec![4], vec![5]];
+        assert_eq!(get_overlapping_groups(input), Ok(expected));
+    }
+
+    #[rstest]
+    #[case("2-x,6-8", ParseAssignmentError::NotANumber { line: 1, column: 3 })]
+    #[case("2-4,6-y", ParseAssignmentError::NotANumber { line: 1, column: 7 })]
+    #[case("2-4;6-8", ParseAssignmentError::BadSeparator { line: 1, column: 4 })]
+    #[case("2-4,6-8x", ParseAssignmentError::BadSeparator { line: 1, column: 8 })]
+    #[case("2-,6-8", ParseAssignmentError::MissingHalf { line: 1, column: 3 })]
+    #[case("2-4,", ParseAssignmentError::MissingHalf { line: 1, column: 5 })]
+    #[case("2-4,-8", ParseAssignmentError::MissingHalf { line: 1, column: 5 })]
+    #[case("2,6-8", ParseAssignmentError::MissingHalf { line: 1, column: 2 })]
+    #[case("2-4,8-6", ParseAssignmentError::Reversed { line: 1, column: 5, range: (8, 6) })]
+    #[case("2-4", ParseAssignmentError::GroupSize { line: 1, expected: 2, found: 1 })]
+    #[case("2-4,6-8,1-1", ParseAssignmentError::GroupSize { line: 1, expected: 2, found: 3 })]
+    fn test_pairassignment_from_string_errors(
+        #[case] input: &str,
+        #[case] expected: ParseAssignmentError,
+    ) {
+        assert_eq!(PairAssignment::from_string(input, 1), Err(expected));
+    }
+
+    #[test]
+    fn test_errors_are_located_by_line() {
+        let input = r#"2-4,6-8
+2-3,4-5
+
+5-7,7-x"#;
+
+        let actual = get_total_number_of_overlapping_assignment_pairs(input);
+
+        assert_eq!(
+            actual,
+            Err(ParseAssignmentError::NotANumber { line: 4, column: 7 })
+        );
+        assert_eq!(
+            actual.unwrap_err().to_string(),
+            "expected a number at line 4, column 7"
+        );
+    }
+
+    #[test]
+    fn test_get_groups_from_input() {
+        let input = r#"2-4,6-8,3-5
+2-3"#;
+
+        let groups = get_groups_from_input(input).unwrap();
+
+        let sizes: Vec<usize> = groups.iter().map(|group| group.len()).collect();
+        assert_eq!(sizes, vec![3, 1]);
+        assert_eq!(
+            get_overlapping_groups(input),
+            Ok(vec![vec![0, 2, 3], vec![1]])
+        );
+        assert_eq!(
+            get_pair_assignments_from_input(input),
+            Err(ParseAssignmentError::GroupSize {
+                line: 1,
+                expected: 2,
+                found: 3
+            })
+        );
     }
 }
@@ -0,0 +1,162 @@
+use std::fmt;
+
+// an inclusive range of section IDs, never empty
+#[derive(Debug, PartialEq, Eq, Clone, Copy, PartialOrd, Ord, Hash)]
+pub struct SectionRange {
+    pub start: u32,
+    pub end: u32,
+}
+
+impl SectionRange {
+    // None when the range would be reversed
+    pub fn new(start: u32, end: u32) -> Option<SectionRange> {
+        (start <= end).then_some(SectionRange { start, end })
+    }
+
+    pub fn length(&self) -> u64 {
+        (self.end - self.start) as u64 + 1
+    }
+
+    pub fn contains(&self, other: &SectionRange) -> bool {
+        self.start <= other.start && other.end <= self.end
+    }
+
+    pub fn overlaps(&self, other: &SectionRange) -> bool {
+        self.start <= other.end && other.start <= self.end
+    }
+
+    // no gap between them, e.g. 2-4 and 5-6
+    fn touches(&self, other: &SectionRange) -> bool {
+        self.overlaps(other)
+            || self.end.checked_add(1) == Some(other.start)
+            || other.end.checked_add(1) == Some(self.start)
+    }
+
+    pub fn intersection(&self, other: &SectionRange) -> Option<SectionRange> {
+        SectionRange::new(self.start.max(other.start), self.end.min(other.end))
+    }
+
+    // only a single range if there's no gap between the two
+    pub fn union(&self, other: &SectionRange) -> Option<SectionRange> {
+        self.touches(other).then(|| SectionRange {
+            start: self.start.min(other.start),
+            end: self.end.max(other.end),
+        })
+    }
+
+    // the sections in self but not in other, which can leave a piece on either side
+    pub fn difference(&self, other: &SectionRange) -> Vec<SectionRange> {
+        if !self.overlaps(other) {
+            return vec![*self];
+        }
+        let mut pieces = vec![];
+        if self.start < other.start {
+            pieces.push(SectionRange {
+                start: self.start,
+                end: other.start - 1,
+            });
+        }
+        if other.end < self.end {
+            pieces.push(SectionRange {
+                start: other.end + 1,
+                end: self.end,
+            });
+        }
+        pieces
+    }
+
+    // the same sections as a sorted list of ranges with gaps between all of them
+    pub fn merge(ranges: &[SectionRange]) -> Vec<SectionRange> {
+        let mut sorted = ranges.to_vec();
+        sorted.sort();
+        let mut merged: Vec<SectionRange> = vec![];
+        for range in sorted {
+            match merged.last().and_then(|last| last.union(&range)) {
+                Some(union) => *merged.last_mut().unwrap() = union,
+                None => merged.push(range),
+            }
+        }
+        merged
+    }
+}
+
+impl fmt::Display for SectionRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}", self.start, self.end)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn range(start: u32, end: u32) -> SectionRange {
+        SectionRange::new(start, end).unwrap()
+    }
+
+    #[test]
+    fn test_new_rejects_reversed() {
+        assert_eq!(SectionRange::new(4, 2), None);
+        assert_eq!(range(3, 3).length(), 1);
+        assert_eq!(range(0, u32::MAX).length(), 1 << 32);
+    }
+
+    #[rstest]
+    #[case(range(2, 6), range(4, 8), Some(range(4, 6)))]
+    #[case(range(2, 4), range(6, 8), None)]
+    #[case(range(5, 7), range(7, 9), Some(range(7, 7)))]
+    #[case(range(2, 8), range(3, 7), Some(range(3, 7)))]
+    fn test_intersection(
+        #[case] a: SectionRange,
+        #[case] b: SectionRange,
+        #[case] expected: Option<SectionRange>,
+    ) {
+        assert_eq!(a.intersection(&b), expected);
+        assert_eq!(b.intersection(&a), expected);
+        assert_eq!(a.overlaps(&b), expected.is_some());
+    }
+
+    #[rstest]
+    #[case(range(2, 6), range(4, 8), Some(range(2, 8)))]
+    #[case(range(2, 3), range(4, 5), Some(range(2, 5)))]
+    #[case(range(2, 4), range(6, 8), None)]
+    fn test_union(
+        #[case] a: SectionRange,
+        #[case] b: SectionRange,
+        #[case] expected: Option<SectionRange>,
+    ) {
+        assert_eq!(a.union(&b), expected);
+        assert_eq!(b.union(&a), expected);
+    }
+
+    #[rstest]
+    #[case(range(2, 8), range(4, 5), vec![range(2, 3), range(6, 8)])]
+    #[case(range(2, 8), range(1, 5), vec![range(6, 8)])]
+    #[case(range(2, 8), range(6, 9), vec![range(2, 5)])]
+    #[case(range(2, 8), range(2, 8), vec![])]
+    #[case(range(2, 4), range(6, 8), vec![range(2, 4)])]
+    fn test_difference(
+        #[case] a: SectionRange,
+        #[case] b: SectionRange,
+        #[case] expected: Vec<SectionRange>,
+    ) {
+        assert_eq!(a.difference(&b), expected);
+    }
+
+    #[test]
+    fn test_merge() {
+        let ranges = [
+            range(7, 9),
+            range(2, 3),
+            range(4, 5),
+            range(12, 12),
+            range(8, 10),
+        ];
+        assert_eq!(
+            SectionRange::merge(&ranges),
+            vec![range(2, 5), range(7, 10), range(12, 12)]
+        );
+        assert_eq!(SectionRange::merge(&[]), vec![]);
+    }
+}
@@ -1,29 +1,33 @@
-use std::collections::HashMap;
+use std::{collections::BTreeMap, error::Error, fmt};
 
-type Stack = Vec<char>;
-type Stacks = HashMap<usize, Stack>;
+// crate labels are usually a single letter but can be any width
+pub type Stack = Vec<String>;
+// keyed by the stack ID from the drawing, in the order they're drawn
+pub type Stacks = BTreeMap<usize, Stack>;
 
 use regex::Regex;
 
+mod search;
+
+pub use search::Search;
+
 fn initialize_stacks_from_input(input: &str) -> Stacks {
-    let num_stacks = get_number_of_stacks(input);
-    let mut stacks: Stacks = HashMap::new();
-    for i in 0..num_stacks {
-        stacks.insert(i + 1, vec![]);
-    }
+    let lines: Vec<&str> = input.split("\n").collect();
+    let columns = get_stack_columns(input);
+    let mut stacks: Stacks = columns.iter().map(|(_, id)| (*id, vec![])).collect();
 
-    for line in input.split("\n").collect::<Vec<&str>>() {
+    // crates sit above their stack ID, so each one goes on the stack whose ID is closest to it
+    // (centres are doubled to keep them whole numbers)
+    for line in lines {
         if get_input_line_type(line) == LineType::Crates {
-            for i in 0..num_stacks {
-                match line.chars().nth(i * 4 + 1) {
-                    Some(' ') => {}
-                    Some(x) => {
-                        if let Some(stack) = stacks.get_mut(&(i + 1)) {
-                            stack.push(x)
-                        }
-                    }
-                    None => {}
-                }
+            for (start, label) in tokens(line, |c| c != ' ') {
+                let centre = 2 * start + label.len();
+                let (_, id) = columns
+                    .iter()
+                    .min_by_key(|(column, id)| centre.abs_diff(2 * column + id.to_string().len()))
+                    .unwrap();
+                let label = label.trim_start_matches('[').trim_end_matches(']');
+                stacks.get_mut(id).unwrap().push(label.to_string());
             }
         }
     }
@@ -33,10 +37,32 @@ fn initialize_stacks_from_input(input: &str) -> Stacks {
     stacks
 }
 
-fn get_number_of_stacks(input: &str) -> usize {
+// the runs of characters matching `keep`, along with the byte offset each one starts at
+fn tokens(line: &str, keep: impl Fn(char) -> bool) -> Vec<(usize, &str)> {
+    let mut tokens = vec![];
+    let mut start = None;
+    // the trailing space closes off a token running to the end of the line
+    for (i, c) in line.char_indices().chain([(line.len(), ' ')]) {
+        match (start, keep(c)) {
+            (None, true) => start = Some(i),
+            (Some(s), false) => {
+                tokens.push((s, &line[s..i]));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    tokens
+}
+
+// where each stack ID starts on the ID line, along with the ID itself
+fn get_stack_columns(input: &str) -> Vec<(usize, usize)> {
     for line in input.split("\n").collect::<Vec<&str>>() {
         if get_input_line_type(line) == LineType::StackIDs {
-            return line.replace(" ", "").len();
+            return tokens(line, |c| !c.is_whitespace())
+                .into_iter()
+                .map(|(column, id)| (column, id.parse().unwrap()))
+                .collect();
         }
     }
 
@@ -64,11 +90,11 @@ fn get_input_line_type(input: &str) -> LineType {
     return LineType::StackIDs;
 }
 
-#[derive(Debug, PartialEq)]
-struct MoveInstruction {
-    from_stack_id: usize,
-    to_stack_id: usize,
-    number_of_crates: usize,
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct MoveInstruction {
+    pub from_stack_id: usize,
+    pub to_stack_id: usize,
+    pub number_of_crates: usize,
 }
 
 impl MoveInstruction {
@@ -92,76 +118,281 @@ fn get_move_instruction_from_line(line: &str) -> MoveInstruction {
     }
 }
 
-fn apply_move_instruction_cratemover9000(
-    stacks: &mut Stacks,
-    move_instruction: MoveInstruction,
-) -> () {
-    for _ in 0..move_instruction.number_of_crates {
-        let from = stacks.get_mut(&move_instruction.from_stack_id).unwrap();
-        let c = from.pop().unwrap();
-        let to = stacks.get_mut(&move_instruction.to_stack_id).unwrap();
-        to.push(c);
+// the starting drawing and the rearrangement procedure, parsed once so it can be run by any crane
+#[derive(Debug, PartialEq, Clone)]
+pub struct Scenario {
+    pub stacks: Stacks,
+    pub moves: Vec<MoveInstruction>,
+}
+
+impl Scenario {
+    pub fn parse(input: &str) -> Scenario {
+        let stacks = initialize_stacks_from_input(input);
+        let moves = input
+            .split("\n")
+            .filter(|line| get_input_line_type(line) == LineType::Move)
+            .map(get_move_instruction_from_line)
+            .collect();
+        Scenario { stacks, moves }
+    }
+
+    pub fn run(&self, crane: &dyn CraneModel) -> Result<Stacks, MoveError> {
+        self.run_with(crane, |_, _| {})
+    }
+
+    // calls `after_move` with every instruction and the stacks it left behind
+    pub fn run_with(
+        &self,
+        crane: &dyn CraneModel,
+        mut after_move: impl FnMut(&MoveInstruction, &Stacks),
+    ) -> Result<Stacks, MoveError> {
+        let mut stacks = self.stacks.clone();
+        for (i, move_instruction) in self.moves.iter().enumerate() {
+            validate_move(&stacks, move_instruction, i + 1)?;
+            crane.apply(&mut stacks, move_instruction);
+            after_move(move_instruction, &stacks);
+        }
+        Ok(stacks)
     }
 }
 
-fn apply_move_instruction_cratemover9001(
-    stacks: &mut Stacks,
-    move_instruction: MoveInstruction,
-) -> () {
-    let mut tmp_crates: Vec<char> = vec![];
-    for _ in 0..move_instruction.number_of_crates {
-        let from = stacks.get_mut(&move_instruction.from_stack_id).unwrap();
-        tmp_crates.push(from.pop().unwrap());
-    }
-    let to = stacks.get_mut(&move_instruction.to_stack_id).unwrap();
-    tmp_crates.reverse();
-    to.append(&mut tmp_crates)
+impl fmt::Display for MoveInstruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "move {} from {} to {}",
+            self.number_of_crates, self.from_stack_id, self.to_stack_id
+        )
+    }
 }
 
-fn apply_move_instruction(
-    stacks: &mut Stacks,
-    move_instruction: MoveInstruction,
-    apply_fn: &dyn Fn(&mut Stacks, MoveInstruction) -> (),
-) {
-    apply_fn(stacks, move_instruction)
+#[derive(Debug, PartialEq)]
+pub enum MoveError {
+    // instructions are numbered from 1 in the order they appear in the input
+    UnknownStack {
+        instruction: usize,
+        stack_id: usize,
+    },
+    NotEnoughCrates {
+        instruction: usize,
+        stack_id: usize,
+        requested: usize,
+        available: usize,
+    },
 }
 
-pub fn run_scenario_cratemover9000(input: &str) -> String {
-    let mut stacks: Stacks = initialize_stacks_from_input(input);
-    for line in input.split("\n").collect::<Vec<&str>>() {
-        if get_input_line_type(line) == LineType::Move {
-            let mi = get_move_instruction_from_line(line);
-            apply_move_instruction(&mut stacks, mi, &apply_move_instruction_cratemover9000);
+impl fmt::Display for MoveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MoveError::UnknownStack {
+                instruction,
+                stack_id,
+            } => write!(
+                f,
+                "instruction {} refers to stack {} which doesn't exist",
+                instruction, stack_id
+            ),
+            MoveError::NotEnoughCrates {
+                instruction,
+                stack_id,
+                requested,
+                available,
+            } => write!(
+                f,
+                "instruction {} moves {} crates from stack {} which only has {}",
+                instruction, requested, stack_id, available
+            ),
         }
     }
-    let mut output = String::from("");
-    for i in 0..stacks.len() {
-        output.push(stacks.get(&(i + 1)).unwrap().to_owned().pop().unwrap());
+}
+
+impl Error for MoveError {}
+
+// every crane takes the crates from the top of one existing stack and puts them on another, so
+// the same checks hold whatever the model
+fn validate_move(
+    stacks: &Stacks,
+    move_instruction: &MoveInstruction,
+    instruction: usize,
+) -> Result<(), MoveError> {
+    if !stacks.contains_key(&move_instruction.to_stack_id) {
+        return Err(MoveError::UnknownStack {
+            instruction,
+            stack_id: move_instruction.to_stack_id,
+        });
     }
-    output
+    let from = stacks
+        .get(&move_instruction.from_stack_id)
+        .ok_or(MoveError::UnknownStack {
+            instruction,
+            stack_id: move_instruction.from_stack_id,
+        })?;
+    if from.len() < move_instruction.number_of_crates {
+        return Err(MoveError::NotEnoughCrates {
+            instruction,
+            stack_id: move_instruction.from_stack_id,
+            requested: move_instruction.number_of_crates,
+            available: from.len(),
+        });
+    }
+    Ok(())
 }
 
-pub fn run_scenario_cratemover9001(input: &str) -> String {
-    let mut stacks: Stacks = initialize_stacks_from_input(input);
-    for line in input.split("\n").collect::<Vec<&str>>() {
-        if get_input_line_type(line) == LineType::Move {
-            let mi = get_move_instruction_from_line(line);
-            apply_move_instruction(&mut stacks, mi, &apply_move_instruction_cratemover9001);
+// how a crane carries out a single move instruction
+pub trait CraneModel {
+    fn apply(&self, stacks: &mut Stacks, move_instruction: &MoveInstruction);
+}
+
+// moves one crate at a time, so the moved crates end up in reverse order
+pub struct CrateMover9000;
+
+// moves all the crates at once, so they keep their order
+pub struct CrateMover9001;
+
+// lifts at most this many crates at a time, each lift keeping its order
+pub struct LimitedLiftCrane {
+    max_lift: usize,
+}
+
+impl LimitedLiftCrane {
+    // None when it couldn't lift anything at all
+    pub fn new(max_lift: usize) -> Option<LimitedLiftCrane> {
+        (max_lift > 0).then_some(LimitedLiftCrane { max_lift })
+    }
+}
+
+// takes the top `number_of_crates` crates off one stack and puts them on another in the same order
+fn lift(stacks: &mut Stacks, from_stack_id: usize, to_stack_id: usize, number_of_crates: usize) {
+    let from = stacks.get_mut(&from_stack_id).unwrap();
+    let mut crates = from.split_off(from.len() - number_of_crates);
+    let to = stacks.get_mut(&to_stack_id).unwrap();
+    to.append(&mut crates)
+}
+
+impl CraneModel for CrateMover9000 {
+    fn apply(&self, stacks: &mut Stacks, move_instruction: &MoveInstruction) {
+        LimitedLiftCrane { max_lift: 1 }.apply(stacks, move_instruction)
+    }
+}
+
+impl CraneModel for CrateMover9001 {
+    fn apply(&self, stacks: &mut Stacks, move_instruction: &MoveInstruction) {
+        lift(
+            stacks,
+            move_instruction.from_stack_id,
+            move_instruction.to_stack_id,
+            move_instruction.number_of_crates,
+        )
+    }
+}
+
+impl CraneModel for LimitedLiftCrane {
+    fn apply(&self, stacks: &mut Stacks, move_instruction: &MoveInstruction) {
+        let mut remaining = move_instruction.number_of_crates;
+        while remaining > 0 {
+            let number_of_crates = remaining.min(self.max_lift);
+            lift(
+                stacks,
+                move_instruction.from_stack_id,
+                move_instruction.to_stack_id,
+                number_of_crates,
+            );
+            remaining -= number_of_crates;
         }
     }
+}
+
+// shown in the top-of-stacks string for a stack that ended up empty
+const EMPTY_STACK: &str = "_";
+
+fn top_of_stacks(stacks: &Stacks) -> String {
     let mut output = String::from("");
-    for i in 0..stacks.len() {
-        output.push(stacks.get(&(i + 1)).unwrap().to_owned().pop().unwrap());
+    for stack in stacks.values() {
+        output.push_str(stack.last().map_or(EMPTY_STACK, |top| top.as_str()));
     }
     output
 }
 
+// the inverse of initialize_stacks_from_input: rows of crates padded to the full width, with the
+// stack IDs underneath
+// every column is as wide as its widest crate or its ID, with crates and IDs centred in it
+pub fn draw_stacks(stacks: &Stacks) -> String {
+    let widths: Vec<usize> = stacks
+        .iter()
+        .map(|(id, stack)| {
+            let widest_crate = stack.iter().map(|label| label.len() + 2).max().unwrap_or(3);
+            widest_crate.max(id.to_string().len())
+        })
+        .collect();
+    let height = stacks.values().map(|stack| stack.len()).max().unwrap_or(0);
+    let mut drawing = String::from("");
+    for level in (0..height).rev() {
+        let row: Vec<String> = stacks
+            .values()
+            .zip(&widths)
+            .map(|(stack, width)| match stack.get(level) {
+                Some(label) => format!("{:^width$}", format!("[{}]", label)),
+                None => " ".repeat(*width),
+            })
+            .collect();
+        drawing.push_str(&row.join(" "));
+        drawing.push('\n');
+    }
+    let ids: Vec<String> = stacks
+        .keys()
+        .zip(&widths)
+        .map(|(id, width)| format!("{:^width$}", id))
+        .collect();
+    drawing.push_str(&ids.join(" "));
+    drawing.push('\n');
+    drawing
+}
+
+// the drawing before the first move and after every move
+pub fn trace_scenario(input: &str, crane: &dyn CraneModel) -> Result<String, MoveError> {
+    let scenario = Scenario::parse(input);
+    let mut trace = draw_stacks(&scenario.stacks);
+    scenario.run_with(crane, |move_instruction, stacks| {
+        trace.push_str(&format!("\n{}\n\n{}", move_instruction, draw_stacks(stacks)));
+    })?;
+    Ok(trace)
+}
+
+// the fewest moves the crane needs to get from the starting drawing to where the procedure ends up
+pub fn optimise_scenario(
+    input: &str,
+    crane: &dyn CraneModel,
+    max_states: usize,
+) -> Result<Search, MoveError> {
+    let scenario = Scenario::parse(input);
+    let goal = scenario.run(crane)?;
+    Ok(search::find_moves(&scenario.stacks, &goal, crane, max_states))
+}
+
+pub fn run_scenario(input: &str, crane: &dyn CraneModel) -> Result<String, MoveError> {
+    let scenario = Scenario::parse(input);
+    Ok(top_of_stacks(&scenario.run(crane)?))
+}
+
+pub fn run_scenario_cratemover9000(input: &str) -> Result<String, MoveError> {
+    run_scenario(input, &CrateMover9000)
+}
+
+pub fn run_scenario_cratemover9001(input: &str) -> Result<String, MoveError> {
+    run_scenario(input, &CrateMover9001)
+}
+
 #[cfg(test)]
 mod tests {
     use rstest::rstest;
 
     use super::*;
 
+    // one single letter crate per character, bottom first
+    fn stack(crates: &str) -> Stack {
+        crates.chars().map(|c| c.to_string()).collect()
+    }
+
     #[test]
     fn test_initialize_stacks() {
         let input = r#"    [D]
@@ -174,10 +405,10 @@ move 3 from 1 to 3
 move 2 from 2 to 1
 move 1 from 1 to 2"#;
 
-        let expected: Stacks = HashMap::from([
-            (1, vec!['Z', 'N']),
-            (2, vec!['M', 'C', 'D']),
-            (3, vec!['P']),
+        let expected: Stacks = Stacks::from([
+            (1, stack("ZN")),
+            (2, stack("MCD")),
+            (3, stack("P")),
         ]);
 
         let actual = initialize_stacks_from_input(input);
@@ -186,7 +417,7 @@ move 1 from 1 to 2"#;
     }
 
     #[test]
-    fn test_get_number_of_stacks() {
+    fn test_get_stack_columns() {
         let input = r#"    [D]
 [N] [C]
 [Z] [M] [P]
@@ -197,9 +428,9 @@ move 3 from 1 to 3
 move 2 from 2 to 1
 move 1 from 1 to 2"#;
 
-        let actual = get_number_of_stacks(input);
+        let actual = get_stack_columns(input);
 
-        assert_eq!(actual, 3);
+        assert_eq!(actual, vec![(1, 1), (5, 2), (9, 3)]);
     }
 
     #[rstest]
@@ -228,7 +459,7 @@ move 1 from 1 to 2"#;
 
         let expected: &str = "CMZ";
 
-        let actual = run_scenario_cratemover9000(input);
+        let actual = run_scenario_cratemover9000(input).unwrap();
 
         assert_eq!(actual, expected);
     }
@@ -247,7 +478,7 @@ move 1 from 1 to 2"#;
 
         let expected: &str = "MCD";
 
-        let actual = run_scenario_cratemover9001(input);
+        let actual = run_scenario_cratemover9001(input).unwrap();
 
         assert_eq!(actual, expected);
     }
@@ -263,20 +494,20 @@ move 1 from 1 to 2"#;
     #[test]
     fn test_apply_move_instruction_cratemover9000_case1() {
         let mut stacks = Stacks::from([
-            (1, vec!['Z', 'N']),
-            (2, vec!['M', 'C', 'D']),
-            (3, vec!['P']),
+            (1, stack("ZN")),
+            (2, stack("MCD")),
+            (3, stack("P")),
         ]);
         let mi = MoveInstruction::new(2, 1, 1);
 
-        apply_move_instruction_cratemover9000(&mut stacks, mi);
+        CrateMover9000.apply(&mut stacks, &mi);
 
         assert_eq!(
             stacks,
             Stacks::from([
-                (1, vec!['Z', 'N', 'D']),
-                (2, vec!['M', 'C']),
-                (3, vec!['P']),
+                (1, stack("ZND")),
+                (2, stack("MC")),
+                (3, stack("P")),
             ])
         );
     }
@@ -284,20 +515,20 @@ move 1 from 1 to 2"#;
     #[test]
     fn test_apply_move_instruction_cratemover9000_case2() {
         let mut stacks = Stacks::from([
-            (1, vec!['Z', 'N', 'D']),
-            (2, vec!['M', 'C']),
-            (3, vec!['P']),
+            (1, stack("ZND")),
+            (2, stack("MC")),
+            (3, stack("P")),
         ]);
         let mi = MoveInstruction::new(1, 3, 3);
 
-        apply_move_instruction(&mut stacks, mi, &apply_move_instruction_cratemover9000);
+        CrateMover9000.apply(&mut stacks, &mi);
 
         assert_eq!(
             stacks,
             Stacks::from([
                 (1, vec![]),
-                (2, vec!['M', 'C']),
-                (3, vec!['P', 'D', 'N', 'Z']),
+                (2, stack("MC")),
+                (3, stack("PDNZ")),
             ])
         );
     }
@@ -305,21 +536,260 @@ move 1 from 1 to 2"#;
     #[test]
     fn test_apply_move_instruction_cratemover9001_case1() {
         let mut stacks = Stacks::from([
-            (1, vec!['Z', 'N', 'D']),
-            (2, vec!['M', 'C']),
-            (3, vec!['P']),
+            (1, stack("ZND")),
+            (2, stack("MC")),
+            (3, stack("P")),
         ]);
         let mi = MoveInstruction::new(1, 3, 3);
 
-        apply_move_instruction(&mut stacks, mi, &apply_move_instruction_cratemover9001);
+        CrateMover9001.apply(&mut stacks, &mi);
 
         assert_eq!(
             stacks,
             Stacks::from([
                 (1, vec![]),
-                (2, vec!['M', 'C']),
-                (3, vec!['P', 'Z', 'N', 'D']),
+                (2, stack("MC")),
+                (3, stack("PZND")),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_parse_scenario() {
+        let input = r#"    [D]
+[N] [C]
+[Z] [M] [P]
+ 1   2   3
+
+move 1 from 2 to 1
+move 3 from 1 to 3"#;
+
+        let actual = Scenario::parse(input);
+
+        assert_eq!(actual.stacks, initialize_stacks_from_input(input));
+        assert_eq!(
+            actual.moves,
+            vec![MoveInstruction::new(2, 1, 1), MoveInstruction::new(1, 3, 3)]
+        );
+    }
+
+    #[rstest]
+    #[case(1, stack("PDNZY"))]
+    #[case(2, stack("PNDYZ"))]
+    #[case(3, stack("PZNDY"))]
+    #[case(5, stack("PYZND"))]
+    fn test_limited_lift_crane(#[case] max_lift: usize, #[case] expected: Stack) {
+        let mut stacks = Stacks::from([(1, stack("YZND")), (2, stack("P"))]);
+        let mi = MoveInstruction::new(1, 2, 4);
+
+        LimitedLiftCrane::new(max_lift)
+            .unwrap()
+            .apply(&mut stacks, &mi);
+
+        assert_eq!(stacks, Stacks::from([(1, vec![]), (2, expected)]));
+    }
+
+    #[test]
+    fn test_limited_lift_crane_rejects_zero() {
+        assert!(LimitedLiftCrane::new(0).is_none());
+    }
+
+    #[test]
+    fn test_compare_crane_models() {
+        let input = r#"    [D]
+[N] [C]
+[Z] [M] [P]
+ 1   2   3
+
+move 1 from 2 to 1
+move 3 from 1 to 3
+move 2 from 2 to 1
+move 1 from 1 to 2"#;
+
+        assert_eq!(
+            run_scenario(input, &LimitedLiftCrane { max_lift: 1 }),
+            run_scenario(input, &CrateMover9000)
+        );
+        assert_eq!(
+            run_scenario(input, &LimitedLiftCrane { max_lift: 3 }),
+            run_scenario(input, &CrateMover9001)
+        );
+        assert_eq!(
+            run_scenario(input, &LimitedLiftCrane { max_lift: 2 }),
+            Ok("MCZ".to_string())
+        );
+    }
+
+    #[rstest]
+    #[case("move 4 from 1 to 2", MoveError::NotEnoughCrates { instruction: 2, stack_id: 1, requested: 4, available: 3 })]
+    #[case("move 1 from 4 to 2", MoveError::UnknownStack { instruction: 2, stack_id: 4 })]
+    #[case("move 1 from 1 to 0", MoveError::UnknownStack { instruction: 2, stack_id: 0 })]
+    fn test_invalid_move(#[case] line: &str, #[case] expected: MoveError) {
+        let input = format!(
+            r#"    [D]
+[N] [C]
+[Z] [M] [P]
+ 1   2   3
+
+move 1 from 2 to 1
+{}
+move 1 from 1 to 2"#,
+            line
+        );
+
+        let actual = run_scenario_cratemover9000(&input);
+
+        assert_eq!(actual, Err(expected));
+    }
+
+    #[test]
+    fn test_move_error_display() {
+        let err = MoveError::NotEnoughCrates {
+            instruction: 3,
+            stack_id: 2,
+            requested: 5,
+            available: 1,
+        };
+        assert_eq!(
+            err.to_string(),
+            "instruction 3 moves 5 crates from stack 2 which only has 1"
+        );
+    }
+
+    #[test]
+    fn test_empty_stack_placeholder() {
+        let input = r#"    [D]
+[N] [C]
+[Z] [M] [P]
+ 1   2   3
+
+move 3 from 2 to 1
+move 1 from 3 to 1"#;
+
+        let actual = run_scenario_cratemover9001(input).unwrap();
+
+        assert_eq!(actual, "P__");
+    }
+
+    #[test]
+    fn test_draw_stacks() {
+        let stacks = Stacks::from([
+            (1, stack("ZN")),
+            (2, stack("MCD")),
+            (3, stack("P")),
+        ]);
+        let expected = "    [D]    \n[N] [C]    \n[Z] [M] [P]\n 1   2   3 \n";
+
+        assert_eq!(draw_stacks(&stacks), expected);
+    }
+
+    #[test]
+    fn test_trace_scenario() {
+        let input = r#"    [D]
+[N] [C]
+[Z] [M] [P]
+ 1   2   3
+
+move 1 from 2 to 1
+move 3 from 1 to 3"#;
+        let expected = r#"    [D]
+[N] [C]
+[Z] [M] [P]
+ 1   2   3
+
+move 1 from 2 to 1
+
+[D]
+[N] [C]
+[Z] [M] [P]
+ 1   2   3
+
+move 3 from 1 to 3
+
+        [Z]
+        [N]
+    [C] [D]
+    [M] [P]
+ 1   2   3
+"#;
+
+        let actual = trace_scenario(input, &CrateMover9000).unwrap();
+
+        // the drawing pads every row to the full width, which is hard to see in the expected text
+        let actual: Vec<&str> = actual.lines().map(str::trim_end).collect();
+        assert_eq!(actual, expected.lines().collect::<Vec<&str>>());
+    }
+
+    #[test]
+    fn test_draw_parse_round_trip() {
+        // a small linear congruential generator, so the cases are random but repeatable
+        let mut seed: u64 = 2022;
+        let mut next = |bound: u64| {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (seed >> 33) % bound
+        };
+        for _ in 0..200 {
+            let num_stacks = next(14) as usize + 1;
+            let stacks: Stacks = (1..=num_stacks)
+                .map(|id| {
+                    let height = next(6) as usize;
+                    let stack = (0..height)
+                        .map(|_| {
+                            let width = next(3) + 1;
+                            (0..width)
+                                .map(|_| (b'A' + next(26) as u8) as char)
+                                .collect()
+                        })
+                        .collect();
+                    (id, stack)
+                })
+                .collect();
+
+            let drawing = draw_stacks(&stacks);
+            let parsed = initialize_stacks_from_input(&drawing);
+
+            assert_eq!(parsed, stacks, "drawing:\n{}", drawing);
+            assert_eq!(draw_stacks(&parsed), drawing);
+        }
+    }
+
+    #[test]
+    fn test_initialize_stacks_beyond_nine() {
+        let input = r#"                                        [K]
+[A] [B] [C] [D] [E] [F] [G] [H] [I] [J] [L]
+ 1   2   3   4   5   6   7   8   9  10  11
+
+move 2 from 11 to 1"#;
+
+        let scenario = Scenario::parse(input);
+
+        assert_eq!(scenario.stacks.len(), 11);
+        assert_eq!(scenario.stacks[&10], stack("J"));
+        assert_eq!(scenario.stacks[&11], stack("LK"));
+        assert_eq!(
+            run_scenario_cratemover9000(input),
+            Ok("LBCDEFGHIJ_".to_string())
+        );
+    }
+
+    #[test]
+    fn test_initialize_stacks_wide_labels() {
+        let input = r#"[CD]
+[AB] [X]  [EFG]
+ 1    2     3
+
+move 1 from 1 to 2"#;
+
+        let scenario = Scenario::parse(input);
+
+        assert_eq!(
+            scenario.stacks,
+            Stacks::from([
+                (1, vec!["AB".to_string(), "CD".to_string()]),
+                (2, vec!["X".to_string()]),
+                (3, vec!["EFG".to_string()]),
             ])
         );
+        assert_eq!(run_scenario_cratemover9000(input), Ok("ABCDEFG".to_string()));
     }
 }
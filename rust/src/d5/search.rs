@@ -0,0 +1,217 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap, HashSet},
+};
+
+use super::{CraneModel, MoveInstruction, Stacks};
+
+#[derive(Debug, PartialEq)]
+pub enum Search {
+    // as few moves as possible, in order
+    Found(Vec<MoveInstruction>),
+    // every arrangement reachable from the start was tried
+    Unreachable,
+    // gave up after generating this many arrangements
+    LimitReached(usize),
+}
+
+// A* over arrangements of crates, every move costs the same
+// a move only ever changes two stacks, so at least half the stacks that don't match the goal
+// yet still need to be touched, which never overestimates
+// `max_states` caps how many arrangements are ever generated, since every one of them is kept
+pub fn find_moves(
+    start: &Stacks,
+    goal: &Stacks,
+    crane: &dyn CraneModel,
+    max_states: usize,
+) -> Search {
+    if !same_crates(start, goal) {
+        return Search::Unreachable;
+    }
+    let heuristic = |stacks: &Stacks| {
+        let mismatched = stacks
+            .iter()
+            .filter(|(id, stack)| goal.get(id) != Some(stack))
+            .count();
+        mismatched.div_ceil(2)
+    };
+
+    // arrangements are referred to by the order they were first seen in
+    let mut states: Vec<Stacks> = vec![start.clone()];
+    let mut ids: HashMap<Stacks, usize> = HashMap::from([(start.clone(), 0)]);
+    let mut costs: Vec<usize> = vec![0];
+    let mut parents: Vec<Option<(usize, MoveInstruction)>> = vec![None];
+    let mut expanded: HashSet<usize> = HashSet::new();
+    let mut queue = BinaryHeap::from([Reverse((heuristic(start), 0, 0))]);
+    while let Some(Reverse((_, cost, id))) = queue.pop() {
+        if states[id] == *goal {
+            return Search::Found(path(&parents, id));
+        }
+        if !expanded.insert(id) {
+            continue;
+        }
+        for move_instruction in possible_moves(&states[id]) {
+            let mut next = states[id].clone();
+            crane.apply(&mut next, &move_instruction);
+            let next_id = match ids.get(&next) {
+                Some(&known) if costs[known] <= cost + 1 => continue,
+                Some(&known) => {
+                    costs[known] = cost + 1;
+                    parents[known] = Some((id, move_instruction));
+                    known
+                }
+                None => {
+                    if states.len() >= max_states {
+                        return Search::LimitReached(max_states);
+                    }
+                    ids.insert(next.clone(), states.len());
+                    states.push(next);
+                    costs.push(cost + 1);
+                    parents.push(Some((id, move_instruction)));
+                    states.len() - 1
+                }
+            };
+            let estimate = cost + 1 + heuristic(&states[next_id]);
+            queue.push(Reverse((estimate, cost + 1, next_id)));
+        }
+    }
+    Search::Unreachable
+}
+
+// moves only rearrange crates, so both sides need the same stacks holding the same crates
+fn same_crates(start: &Stacks, goal: &Stacks) -> bool {
+    let crates = |stacks: &Stacks| {
+        let mut crates: Vec<String> = stacks.values().flatten().cloned().collect();
+        crates.sort();
+        crates
+    };
+    start.keys().eq(goal.keys()) && crates(start) == crates(goal)
+}
+
+fn possible_moves(stacks: &Stacks) -> Vec<MoveInstruction> {
+    let mut moves = vec![];
+    for (&from_stack_id, from) in stacks {
+        for &to_stack_id in stacks.keys().filter(|&&id| id != from_stack_id) {
+            for number_of_crates in 1..=from.len() {
+                moves.push(MoveInstruction::new(
+                    from_stack_id,
+                    to_stack_id,
+                    number_of_crates,
+                ));
+            }
+        }
+    }
+    moves
+}
+
+fn path(parents: &[Option<(usize, MoveInstruction)>], goal: usize) -> Vec<MoveInstruction> {
+    let mut moves = vec![];
+    let mut id = goal;
+    while let Some((previous, move_instruction)) = parents[id] {
+        moves.push(move_instruction);
+        id = previous;
+    }
+    moves.reverse();
+    moves
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{CrateMover9000, CrateMover9001, Scenario};
+    use super::*;
+
+    const INPUT: &str = r#"    [D]
+[N] [C]
+[Z] [M] [P]
+ 1   2   3
+
+move 1 from 2 to 1
+move 3 from 1 to 3
+move 2 from 2 to 1
+move 1 from 1 to 2"#;
+
+    #[test]
+    fn test_find_moves_beats_the_procedure() {
+        let scenario = Scenario::parse(INPUT);
+        for crane in [&CrateMover9000 as &dyn CraneModel, &CrateMover9001] {
+            let goal = scenario.run(crane).unwrap();
+
+            let Search::Found(moves) = find_moves(&scenario.stacks, &goal, crane, 10_000) else {
+                panic!("no moves found");
+            };
+
+            assert!(moves.len() <= scenario.moves.len());
+            let shortest = Scenario {
+                stacks: scenario.stacks.clone(),
+                moves,
+            };
+            assert_eq!(shortest.run(crane), Ok(goal));
+        }
+    }
+
+    #[test]
+    fn test_find_moves_depends_on_crane() {
+        let ab = vec!["A".to_string(), "B".to_string()];
+        let start = Stacks::from([(1, ab.clone()), (2, vec![]), (3, vec![])]);
+        let goal = Stacks::from([(1, vec![]), (2, ab.clone()), (3, vec![])]);
+
+        // the 9001 keeps the order, the 9000 reverses them twice
+        assert_eq!(
+            find_moves(&start, &goal, &CrateMover9001, 100),
+            Search::Found(vec![MoveInstruction::new(1, 2, 2)])
+        );
+        assert_eq!(
+            find_moves(&start, &goal, &CrateMover9000, 100),
+            Search::Found(vec![
+                MoveInstruction::new(1, 3, 2),
+                MoveInstruction::new(3, 2, 2),
+            ])
+        );
+
+        // without a spare stack the 9000 can never swap them back
+        let start = Stacks::from([(1, ab.clone()), (2, vec![])]);
+        let goal = Stacks::from([(1, vec![]), (2, ab)]);
+        assert_eq!(
+            find_moves(&start, &goal, &CrateMover9000, 100),
+            Search::Unreachable
+        );
+    }
+
+    #[test]
+    fn test_find_moves_unreachable() {
+        let start = Stacks::from([(1, vec!["A".to_string()]), (2, vec![])]);
+        let goal = Stacks::from([(1, vec![]), (2, vec!["B".to_string()])]);
+
+        assert_eq!(
+            find_moves(&start, &goal, &CrateMover9000, 100),
+            Search::Unreachable
+        );
+    }
+
+    #[test]
+    fn test_find_moves_limit() {
+        let scenario = Scenario::parse(INPUT);
+        let goal = scenario.run(&CrateMover9000).unwrap();
+
+        assert_eq!(
+            find_moves(&scenario.stacks, &goal, &CrateMover9000, 1),
+            Search::LimitReached(1)
+        );
+    }
+
+    #[test]
+    fn test_find_moves_limit_bounds_generated_states() {
+        // nine tall stacks can reach an enormous number of arrangements in a single move
+        let stacks: Stacks = (1..=9)
+            .map(|id| (id, (0..20).map(|n| format!("{}{}", id, n)).collect()))
+            .collect();
+        let mut goal = stacks.clone();
+        goal.get_mut(&1).unwrap().reverse();
+
+        // the start alone has 9 * 8 * 20 next arrangements, more than the limit allows
+        assert_eq!(
+            find_moves(&stacks, &goal, &CrateMover9001, 1_000),
+            Search::LimitReached(1_000)
+        );
+    }
+}
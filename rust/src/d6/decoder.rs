@@ -0,0 +1,145 @@
+use std::ops::Range;
+
+use super::markers;
+
+// a marker is a run of `size` distinct characters and starts a frame of the given kind
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Marker<K> {
+    pub kind: K,
+    pub size: usize,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum FrameKind {
+    PacketHeader,
+    MessageBody,
+}
+
+// the device's protocol: a start-of-packet marker followed by a start-of-message marker
+pub const DEVICE_PROTOCOL: [Marker<FrameKind>; 2] = [
+    Marker {
+        kind: FrameKind::PacketHeader,
+        size: 4,
+    },
+    Marker {
+        kind: FrameKind::MessageBody,
+        size: 14,
+    },
+];
+
+// offsets are in characters from the start of the stream
+#[derive(Debug, PartialEq, Clone)]
+pub struct Frame<K> {
+    pub kind: K,
+    pub marker: Range<usize>,
+    // from the end of the marker up to the start of the next one, or the end of the stream
+    pub body: Range<usize>,
+}
+
+// looks for the protocol's markers in turn, starting over with the first one after the last,
+// each search starting where the previous marker ended
+// anything before the first marker isn't part of a frame, and decoding stops at the first
+// marker that never shows up, leaving the last frame running to the end of the stream
+pub fn decode<K: Copy>(input: &str, protocol: &[Marker<K>]) -> Vec<Frame<K>> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut frames: Vec<Frame<K>> = vec![];
+    let mut offset = 0;
+    for marker in protocol.iter().cycle() {
+        let Some(end) = markers(chars[offset..].iter().copied(), marker.size).next() else {
+            break;
+        };
+        let end = offset + end;
+        let start = end - marker.size;
+        if let Some(previous) = frames.last_mut() {
+            previous.body.end = start;
+        }
+        frames.push(Frame {
+            kind: marker.kind,
+            marker: start..end,
+            body: end..chars.len(),
+        });
+        offset = end;
+    }
+    frames
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_device_protocol() {
+        let input = "mjqjpqmgbljsphdztnvjfqwrcgsmlb";
+
+        let frames = decode(input, &DEVICE_PROTOCOL);
+
+        assert_eq!(
+            frames,
+            vec![
+                Frame {
+                    kind: FrameKind::PacketHeader,
+                    marker: 3..7,
+                    body: 7..11,
+                },
+                Frame {
+                    kind: FrameKind::MessageBody,
+                    marker: 11..25,
+                    body: 25..25,
+                },
+                Frame {
+                    kind: FrameKind::PacketHeader,
+                    marker: 25..29,
+                    body: 29..30,
+                },
+            ]
+        );
+    }
+
+    #[derive(Debug, PartialEq, Clone, Copy)]
+    enum Synthetic {
+        Start,
+        Data,
+    }
+
+    #[test]
+    fn test_decode_synthetic_protocol() {
+        let protocol = [
+            Marker {
+                kind: Synthetic::Start,
+                size: 2,
+            },
+            Marker {
+                kind: Synthetic::Data,
+                size: 3,
+            },
+        ];
+        // the data marker can't start until the start marker has ended, and vice versa
+        let input = "aabxxxcdeeeefgh";
+
+        let frames = decode(input, &protocol);
+
+        let boundaries: Vec<(Synthetic, &str, &str)> = frames
+            .iter()
+            .map(|frame| {
+                (
+                    frame.kind,
+                    &input[frame.marker.clone()],
+                    &input[frame.body.clone()],
+                )
+            })
+            .collect();
+        assert_eq!(
+            boundaries,
+            vec![
+                (Synthetic::Start, "ab", "xx"),
+                (Synthetic::Data, "xcd", "eee"),
+                (Synthetic::Start, "ef", "gh"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_decode_without_markers() {
+        assert_eq!(decode("aaaa", &DEVICE_PROTOCOL), vec![]);
+    }
+}
@@ -1,13 +1,172 @@
-use std::collections::HashSet;
+use std::{
+    collections::{HashMap, VecDeque},
+    hash::Hash,
+    io::{self, BufReader, Read},
+};
 
-pub fn find_marker(input: &str, num_distint_chars: usize) -> usize {
-    for i in 0..input.len() {
-        let hs: HashSet<char> = HashSet::from_iter(input[i..i + num_distint_chars].chars());
-        if hs.len() == num_distint_chars {
-            return i + num_distint_chars;
+mod decoder;
+
+pub use decoder::{decode, DEVICE_PROTOCOL};
+
+// the last `size` items along with how often each of them appears, so sliding along by one only
+// has to update the counts of the item coming in and the one dropping out
+struct Window<T> {
+    size: usize,
+    items: VecDeque<T>,
+    counts: HashMap<T, usize>,
+}
+
+impl<T: Copy + Eq + Hash> Window<T> {
+    fn new(size: usize) -> Self {
+        Window {
+            size,
+            items: VecDeque::with_capacity(size + 1),
+            counts: HashMap::new(),
+        }
+    }
+
+    // true once the window is full and every item in it is different
+    fn push(&mut self, item: T) -> bool {
+        self.items.push_back(item);
+        *self.counts.entry(item).or_insert(0) += 1;
+        if self.items.len() > self.size {
+            let old = self.items.pop_front().unwrap();
+            let count = self.counts.get_mut(&old).unwrap();
+            *count -= 1;
+            if *count == 0 {
+                self.counts.remove(&old);
+            }
+        }
+        self.items.len() == self.size && self.counts.len() == self.size
+    }
+}
+
+// the end of every window of `size` distinct items, i.e. how many items had been read when the
+// marker was complete
+pub struct Markers<I: Iterator> {
+    items: I,
+    window: Window<I::Item>,
+    position: usize,
+}
+
+impl<I> Iterator for Markers<I>
+where
+    I: Iterator,
+    I::Item: Copy + Eq + Hash,
+{
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        for item in self.items.by_ref() {
+            self.position += 1;
+            if self.window.push(item) {
+                return Some(self.position);
+            }
+        }
+        None
+    }
+}
+
+pub fn markers<I>(items: I, size: usize) -> Markers<I::IntoIter>
+where
+    I: IntoIterator,
+    I::Item: Copy + Eq + Hash,
+{
+    Markers {
+        items: items.into_iter(),
+        window: Window::new(size),
+        position: 0,
+    }
+}
+
+// positions are counted in characters, not bytes
+pub fn find_marker(input: &str, num_distint_chars: usize) -> Option<usize> {
+    markers(input.chars(), num_distint_chars).next()
+}
+
+pub fn find_all_markers(input: &str, num_distint_chars: usize) -> Vec<usize> {
+    markers(input.chars(), num_distint_chars).collect()
+}
+
+// the same as Window but for bytes, keeping the counts in a fixed table instead of a map
+struct ByteWindow {
+    size: usize,
+    items: VecDeque<u8>,
+    counts: [usize; 256],
+    num_distinct: usize,
+}
+
+impl ByteWindow {
+    fn new(size: usize) -> Self {
+        ByteWindow {
+            size,
+            items: VecDeque::with_capacity(size + 1),
+            counts: [0; 256],
+            num_distinct: 0,
+        }
+    }
+
+    fn push(&mut self, byte: u8) -> bool {
+        self.items.push_back(byte);
+        self.counts[byte as usize] += 1;
+        if self.counts[byte as usize] == 1 {
+            self.num_distinct += 1;
+        }
+        if self.items.len() > self.size {
+            let old = self.items.pop_front().unwrap() as usize;
+            self.counts[old] -= 1;
+            if self.counts[old] == 0 {
+                self.num_distinct -= 1;
+            }
+        }
+        self.items.len() == self.size && self.num_distinct == self.size
+    }
+}
+
+// the end of every marker in a stream, which is read a byte at a time without holding more than
+// the window in memory; stops after the first read error
+pub struct ReaderMarkers<R: Read> {
+    bytes: io::Bytes<BufReader<R>>,
+    window: ByteWindow,
+    position: usize,
+    failed: bool,
+}
+
+impl<R: Read> Iterator for ReaderMarkers<R> {
+    type Item = io::Result<usize>;
+
+    fn next(&mut self) -> Option<io::Result<usize>> {
+        if self.failed {
+            return None;
         }
+        for byte in self.bytes.by_ref() {
+            let byte = match byte {
+                Ok(byte) => byte,
+                Err(e) => {
+                    self.failed = true;
+                    return Some(Err(e));
+                }
+            };
+            self.position += 1;
+            if self.window.push(byte) {
+                return Some(Ok(self.position));
+            }
+        }
+        None
+    }
+}
+
+pub fn markers_in<R: Read>(reader: R, num_distinct_bytes: usize) -> ReaderMarkers<R> {
+    ReaderMarkers {
+        bytes: BufReader::new(reader).bytes(),
+        window: ByteWindow::new(num_distinct_bytes),
+        position: 0,
+        failed: false,
     }
-    panic!()
+}
+
+pub fn find_marker_in<R: Read>(reader: R, num_distinct_bytes: usize) -> io::Result<Option<usize>> {
+    markers_in(reader, num_distinct_bytes).next().transpose()
 }
 
 #[cfg(test)]
@@ -22,7 +181,7 @@ mod tests {
     #[case("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg", 10)]
     #[case("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw", 11)]
     fn test_find_marker_part1(#[case] input: &str, #[case] expected: usize) {
-        assert_eq!(find_marker(input, 4), expected);
+        assert_eq!(find_marker(input, 4), Some(expected));
     }
 
     #[rstest]
@@ -32,6 +191,69 @@ mod tests {
     #[case("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg", 29)]
     #[case("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw", 26)]
     fn test_find_marker_part2(#[case] input: &str, #[case] expected: usize) {
-        assert_eq!(find_marker(input, 14), expected);
+        assert_eq!(find_marker(input, 14), Some(expected));
+    }
+
+    #[rstest]
+    #[case("", 4)]
+    #[case("abc", 4)]
+    #[case("aabbccddaabb", 3)]
+    fn test_find_marker_none(#[case] input: &str, #[case] size: usize) {
+        assert_eq!(find_marker(input, size), None);
+    }
+
+    #[test]
+    fn test_find_all_markers() {
+        assert_eq!(find_all_markers("aabcdaab", 3), vec![4, 5, 6]);
+        assert_eq!(find_all_markers("abcd", 1), vec![1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_find_marker_non_ascii() {
+        assert_eq!(find_marker("ééàüöü", 3), Some(4));
+    }
+
+    #[rstest]
+    #[case("mjqjpqmgbljsphdztnvjfqwrcgsmlb", 7)]
+    #[case("bvwbjplbgvbhsrlpgdmjqwftvncz", 5)]
+    fn test_find_marker_in_reader(#[case] input: &str, #[case] expected: usize) {
+        assert_eq!(find_marker_in(input.as_bytes(), 4).unwrap(), Some(expected));
+        assert_eq!(find_marker_in(input.as_bytes(), 27).unwrap(), None);
+    }
+
+    #[test]
+    fn test_markers_in_reader() {
+        let markers: Vec<usize> = markers_in("aabcdaab".as_bytes(), 3)
+            .collect::<io::Result<_>>()
+            .unwrap();
+        assert_eq!(markers, find_all_markers("aabcdaab", 3));
+        // every byte value counts as its own item
+        let bytes: Vec<u8> = (0..=255).chain(0..=255).collect();
+        assert_eq!(markers_in(bytes.as_slice(), 256).count(), 257);
+    }
+
+    // hands out the given bytes and then fails
+    struct Failing<'a>(&'a [u8]);
+
+    impl Read for Failing<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if self.0.is_empty() {
+                return Err(io::Error::other("disconnected"));
+            }
+            let n = self.0.read(buf)?;
+            Ok(n)
+        }
+    }
+
+    #[test]
+    fn test_markers_in_reader_stops_at_error() {
+        let mut markers = markers_in(Failing(b"abcabc"), 3);
+        let found: Vec<usize> = markers.by_ref().take(4).map(Result::unwrap).collect();
+        assert_eq!(found, vec![3, 4, 5, 6]);
+        assert!(markers.next().unwrap().is_err());
+        assert!(markers.next().is_none());
+        let mut markers = markers_in(Failing(b"aa"), 3);
+        assert!(markers.next().unwrap().is_err());
+        assert!(markers.next().is_none());
     }
 }
@@ -1,112 +1,222 @@
-use std::{collections::HashSet, hash::Hash, rc::Rc};
+use std::{
+    collections::HashSet,
+    error::Error,
+    fmt,
+    io::{self, Write},
+};
+
+mod motion;
+mod render;
+
+pub use motion::{Direction, Motion, ParseMotionError};
+pub use render::{Granularity, RenderOptions};
 
 #[derive(PartialEq, Debug, Default, Clone, Copy)]
-struct Point {
-    x: i32,
-    y: i32,
+pub struct Point {
+    pub x: i32,
+    pub y: i32,
 }
 
 impl Point {
     fn from_tuple(xy: (i32, i32)) -> Point {
         Point { x: xy.0, y: xy.1 }
     }
-    fn motion(&mut self, input: &str) {
-        let m: Vec<&str> = input.split(" ").collect::<Vec<&str>>();
-        let size: i32 = m[1].parse::<i32>().unwrap();
-        match m[0] {
-            "R" => self.x += size,
-            "L" => self.x -= size,
-            "U" => self.y += size,
-            "D" => self.y -= size,
-            x => {
-                panic!("Got {:?} for move instruction", x)
-            },
+
+    fn do_move(&mut self, direction: Direction) {
+        let (dx, dy) = direction.delta();
+        self.x += dx;
+        self.y += dy;
+    }
+
+    fn get_xy(&self) -> (i32, i32) {
+        (self.x, self.y)
+    }
+}
+
+// decides where a knot ends up after the knot in front of it has moved
+pub trait FollowRule {
+    fn follow(&self, knot: Point, leader: Point) -> Point;
+}
+
+impl<F: Fn(Point, Point) -> Point> FollowRule for F {
+    fn follow(&self, knot: Point, leader: Point) -> Point {
+        self(knot, leader)
+    }
+}
+
+// the knot stays put while it's within this many steps of its leader (diagonals count as one)
+// and otherwise takes a single step towards it, diagonally if they aren't in the same row or column
+// the puzzle's rope is Slack(1)
+pub struct Slack(pub i32);
+
+impl FollowRule for Slack {
+    fn follow(&self, knot: Point, leader: Point) -> Point {
+        let dx = leader.x - knot.x;
+        let dy = leader.y - knot.y;
+        if dx.abs() > self.0 || dy.abs() > self.0 {
+            Point {
+                x: knot.x + dx.signum(),
+                y: knot.y + dy.signum(),
+            }
+        } else {
+            knot
         }
     }
+}
+
+pub struct Rope<R: FollowRule = Slack> {
+    knots: Vec<Point>,
+    // history[k] holds every position knot k has been in, starting with the origin
+    history: Vec<Vec<Point>>,
+    // every motion that was run along with the number of steps taken once it finished
+    motions: Vec<(Motion, usize)>,
+    rule: R,
+}
+
+impl Rope {
+    pub fn new(num_knots: usize) -> Self {
+        Rope::with_rule(num_knots, Slack(1))
+    }
+}
 
-    fn do_move(&mut self, input: &str) {
-        match input {
-            "R" => self.x += 1,
-            "L" => self.x -= 1,
-            "U" => self.y += 1,
-            "D" => self.y -= 1,
-            x => {
-                panic!("Got {:?} for move instruction", x)
-            },
+impl<R: FollowRule> Rope<R> {
+    pub fn with_rule(num_knots: usize, rule: R) -> Self {
+        Rope {
+            knots: vec![Point::from_tuple((0, 0)); num_knots],
+            history: vec![vec![Point::from_tuple((0, 0))]; num_knots],
+            motions: vec![],
+            rule,
         }
     }
 
-    fn follow(&mut self, leader: &Point) {
-        let dx = leader.x - self.x;
-        let dy = leader.y - self.y;
-        if dx.abs() + dy.abs() > 2 {
-            // diagonal move required
-            // diagonal move is a (1,1) move in the dx/dy direction
-            self.x += if dx > 0 { 1 } else { -1 };
-            self.y += if dy > 0 { 1 } else { -1 };
+    // moves the head a single step and lets the rest of the rope catch up
+    fn step(&mut self, direction: Direction) {
+        self.knots[0].do_move(direction);
+        for i in 1..self.knots.len() {
+            self.knots[i] = self.rule.follow(self.knots[i], self.knots[i - 1]);
+        }
+        for (knot, history) in self.knots.iter().zip(self.history.iter_mut()) {
+            history.push(*knot);
+        }
+    }
 
-        } else if dx.abs() > 1 {
-            self.x += if dx > 0 { 1 } else { -1 };
+    pub fn apply(&mut self, motion: Motion) {
+        for _ in 0..motion.steps {
+            self.step(motion.direction);
+        }
+        self.motions.push((motion, self.history[0].len() - 1));
+    }
 
-        } else if dy.abs() > 1 {
-            self.y += if dy > 0 { 1 } else { -1 };
+    pub fn run(&mut self, input: &str) -> Result<(), ParseMotionError> {
+        for motion in motion::parse_motions(input)? {
+            self.apply(motion);
         }
+        Ok(())
     }
 
-    fn get_xy(&self) -> (i32, i32) {
-        (self.x, self.y)
+    pub fn knots(&self) -> &[Point] {
+        &self.knots
+    }
+
+    // one entry per step, the first one being the starting position
+    pub fn history(&self, knot: usize) -> &[Point] {
+        &self.history[knot]
     }
+
+    pub fn motions(&self) -> &[(Motion, usize)] {
+        &self.motions
+    }
+
+    pub fn tail_visited(&self) -> HashSet<(i32, i32)> {
+        self.history
+            .last()
+            .unwrap()
+            .iter()
+            .map(|point| point.get_xy())
+            .collect()
+    }
+}
+
+fn short_rope(input: &str) -> Result<HashSet<(i32, i32)>, ParseMotionError> {
+    let mut rope = Rope::new(2);
+    rope.run(input)?;
+    Ok(rope.tail_visited())
+}
+
+fn long_rope(input: &str) -> Result<HashSet<(i32, i32)>, ParseMotionError> {
+    let mut rope = Rope::new(10);
+    rope.run(input)?;
+    Ok(rope.tail_visited())
+}
+
+pub fn short_rope_unique_locations(input: &str) -> Result<usize, ParseMotionError> {
+    let hs = short_rope(input)?;
+    Ok(hs.len())
 }
 
-fn short_rope(input: &str) -> HashSet<(i32, i32)> {
-    let mut head = Point::from_tuple((0,0));
-    let mut tail = Point::from_tuple((0,0));
-    let mut visited: HashSet<(i32, i32)> = vec![].into_iter().collect();
+pub fn long_rope_unique_locations(input: &str) -> Result<usize, ParseMotionError> {
+    let hs = long_rope(input)?;
+    Ok(hs.len())
+}
+
+// the cells the tail visited, drawn the way the puzzle does
+pub fn draw_visited(input: &str, num_knots: usize) -> Result<String, ParseMotionError> {
+    let mut rope = Rope::new(num_knots);
+    rope.run(input)?;
+    Ok(render::visited(&rope))
+}
 
-    for line in input.split("\n").collect::<Vec<&str>>() {
-        let m = line.split(" ").collect::<Vec<&str>>();
-        let move_size = m[1].parse().unwrap();
-        for _ in 0..move_size {
-            head.do_move(m[0]);
-            tail.follow(&head);
-            visited.insert(tail.get_xy());
+#[derive(Debug)]
+pub enum RenderError {
+    Parse(ParseMotionError),
+    Write(io::Error),
+}
+
+impl fmt::Display for RenderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RenderError::Parse(e) => write!(f, "{}", e),
+            RenderError::Write(e) => write!(f, "couldn't write the frames: {}", e),
         }
     }
-    visited
 }
 
-fn long_rope(input: &str) -> HashSet<(i32, i32)> {
-    let knots = 10;
-    let mut rope: Vec<Point> = vec![];
-    for _ in 0..knots {
-        rope.push(Point::from_tuple((0,0)));
-    }
+impl Error for RenderError {}
 
-    let mut visited: HashSet<(i32, i32)> = vec![].into_iter().collect();
+impl From<ParseMotionError> for RenderError {
+    fn from(e: ParseMotionError) -> Self {
+        RenderError::Parse(e)
+    }
+}
 
-    for line in input.split("\n").collect::<Vec<&str>>() {
-        let m = line.split(" ").collect::<Vec<&str>>();
-        let move_size = m[1].parse().unwrap();
-        for _ in 0..move_size {
-            rope[0].do_move(m[0]);
-            for i in 1..rope.len() {
-                let prev_rope = Rc::new(rope[i-1]);
-                rope[i].follow(&prev_rope);
-            }
-            visited.insert(rope.last().unwrap().get_xy());
-        }
+impl From<io::Error> for RenderError {
+    fn from(e: io::Error) -> Self {
+        RenderError::Write(e)
     }
-    visited
 }
 
-pub fn short_rope_unique_locations(input: &str) -> usize {
-    let hs = short_rope(input);
-    hs.len()
+// every motion followed by the rope after it, or after each of its steps
+pub fn draw_rope(
+    input: &str,
+    num_knots: usize,
+    options: &RenderOptions,
+    out: &mut impl Write,
+) -> Result<(), RenderError> {
+    let mut rope = Rope::new(num_knots);
+    rope.run(input)?;
+    Ok(render::transcript(&rope, options, out)?)
 }
 
-pub fn long_rope_unique_locations(input: &str) -> usize {
-    let hs = long_rope(input);
-    hs.len()
+// the same frames with terminal escape codes in between, meant to be played back with cat
+pub fn animate_rope(
+    input: &str,
+    num_knots: usize,
+    options: &RenderOptions,
+    out: &mut impl Write,
+) -> Result<(), RenderError> {
+    let mut rope = Rope::new(num_knots);
+    rope.run(input)?;
+    Ok(render::animation(&rope, options, out)?)
 }
 
 #[cfg(test)]
@@ -120,9 +230,13 @@ mod tests {
     #[case("U", Point{x:0, y:1})]
     #[case("L", Point{x:-1, y:0})]
     #[case("D", Point{x:0, y:-1})]
+    #[case("UR", Point{x:1, y:1})]
+    #[case("UL", Point{x:-1, y:1})]
+    #[case("DL", Point{x:-1, y:-1})]
+    #[case("DR", Point{x:1, y:-1})]
     fn test_move_head(#[case] input: &str, #[case] expected: Point) {
         let mut head = Point { x: 0, y: 0 };
-        head.do_move(input);
+        head.do_move(Direction::parse(input).unwrap());
         assert_eq!(head, expected);
     }
 
@@ -146,7 +260,7 @@ mod tests {
 
         let mut tail = Point::from_tuple((0, 0));
         let expected = Point::from_tuple(tail_final);
-        tail.follow(&head);
+        tail = Slack(1).follow(tail, head);
         assert_eq!(tail, expected);
     }
 
@@ -161,7 +275,7 @@ D 1
 L 5
 R 2"#;
 
-        let actual = short_rope(&input);
+        let actual = short_rope(&input).unwrap();
         let expected: HashSet<(i32, i32)> = vec![
             (0,0),
             (1,0),
@@ -191,7 +305,7 @@ D 1
 L 5
 R 2"#;
         let expected = 13;
-        let actual = short_rope_unique_locations(&input);
+        let actual = short_rope_unique_locations(&input).unwrap();
         assert_eq!(actual, expected);
     }
 
@@ -206,7 +320,122 @@ D 10
 L 25
 U 20"#;
         let expected = 36;
-        let actual = long_rope_unique_locations(&input);
+        let actual = long_rope_unique_locations(&input).unwrap();
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_rope_history() {
+        let mut rope = Rope::new(3);
+        rope.run("R 3\nU 1").unwrap();
+        let head: Vec<(i32, i32)> = rope.history(0).iter().map(Point::get_xy).collect();
+        assert_eq!(head, vec![(0, 0), (1, 0), (2, 0), (3, 0), (3, 1)]);
+        let tail: Vec<(i32, i32)> = rope.history(2).iter().map(Point::get_xy).collect();
+        assert_eq!(tail, vec![(0, 0), (0, 0), (0, 0), (1, 0), (1, 0)]);
+        assert_eq!(rope.knots()[1], Point { x: 2, y: 0 });
+    }
+
+    #[rstest]
+    #[case((3,0), (1,0))]
+    #[case((3,3), (1,1))]
+    #[case((-3,1), (-1,1))]
+    #[case((0,-2), (0,0))]
+    fn test_follow_with_slack(#[case] head_location: (i32, i32), #[case] tail_final: (i32, i32)) {
+        let actual = Slack(2).follow(Point::default(), Point::from_tuple(head_location));
+        assert_eq!(actual, Point::from_tuple(tail_final));
+    }
+
+    #[test]
+    fn test_custom_follow_rule() {
+        // a knot that never moves diagonally, only along the axis where it's furthest behind
+        let mut rope = Rope::with_rule(2, |knot: Point, leader: Point| {
+            let dx = leader.x - knot.x;
+            let dy = leader.y - knot.y;
+            if dx.abs() <= 1 && dy.abs() <= 1 {
+                knot
+            } else if dx.abs() >= dy.abs() {
+                Point {
+                    x: knot.x + dx.signum(),
+                    y: knot.y,
+                }
+            } else {
+                Point {
+                    x: knot.x,
+                    y: knot.y + dy.signum(),
+                }
+            }
+        });
+        rope.run("R 2\nU 2").unwrap();
+        assert_eq!(rope.knots()[1], Point { x: 1, y: 1 });
+    }
+
+    #[test]
+    fn test_slack_rope_visits_fewer_places() {
+        let input = r#"R 4
+U 4
+L 3
+D 1
+R 4
+D 1
+L 5
+R 2"#;
+        let mut rope = Rope::with_rule(2, Slack(2));
+        rope.run(input).unwrap();
+        assert!(rope.tail_visited().len() < short_rope_unique_locations(input).unwrap());
+    }
+
+    /* DIAGONAL HEAD MOVES */
+    #[rstest]
+    // a diagonal step straight away from the tail drags it along the same diagonal
+    #[case("UR 3", 2, vec![(2, 2)])]
+    // the head passes the tail on a diagonal and leaves it behind without it moving
+    #[case("UR 1\nDL 2", 2, vec![(0, 0)])]
+    // a diagonal step off a straight line pulls the tail diagonally
+    #[case("R 1\nUR 1", 2, vec![(1, 1)])]
+    #[case("UR 4", 10, vec![(3, 3), (2, 2), (1, 1), (0, 0), (0, 0), (0, 0), (0, 0), (0, 0), (0, 0)])]
+    fn test_diagonal_head_moves(
+        #[case] input: &str,
+        #[case] num_knots: usize,
+        #[case] expected: Vec<(i32, i32)>,
+    ) {
+        let mut rope = Rope::new(num_knots);
+        rope.run(input).unwrap();
+        let actual: Vec<(i32, i32)> = rope.knots()[1..].iter().map(Point::get_xy).collect();
         assert_eq!(actual, expected);
     }
+
+    #[test]
+    fn test_diagonal_scenario_unique_locations() {
+        let input = r#"UR 4
+R 2
+DL 3
+UL 2"#;
+        let mut rope = Rope::new(2);
+        rope.run(input).unwrap();
+        let expected: HashSet<(i32, i32)> = vec![
+            (0, 0),
+            (1, 1),
+            (2, 2),
+            (3, 3),
+            (4, 4),
+            (5, 4),
+            (4, 3),
+            (3, 2),
+            (2, 3),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(rope.tail_visited(), expected);
+    }
+
+    #[test]
+    fn test_bad_line_is_an_error() {
+        assert_eq!(
+            short_rope_unique_locations("R 4\nF 2"),
+            Err(ParseMotionError::Direction {
+                line: 2,
+                direction: "F".to_string()
+            })
+        );
+    }
 }
@@ -0,0 +1,190 @@
+use std::{error::Error, fmt};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+    UpLeft,
+    UpRight,
+    DownLeft,
+    DownRight,
+}
+
+// one line of input, e.g. "R 4" or "UL 2"
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Motion {
+    pub direction: Direction,
+    pub steps: usize,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ParseMotionError {
+    // lines are 1-based so they match what an editor shows
+    MissingSteps { line: usize },
+    Direction { line: usize, direction: String },
+    Steps { line: usize, steps: String },
+}
+
+impl Direction {
+    const ALL: [Direction; 8] = [
+        Direction::Up,
+        Direction::Down,
+        Direction::Left,
+        Direction::Right,
+        Direction::UpLeft,
+        Direction::UpRight,
+        Direction::DownLeft,
+        Direction::DownRight,
+    ];
+
+    pub fn parse(input: &str) -> Option<Direction> {
+        // the vertical half can come first or second, "UR" and "RU" are the same move
+        Direction::ALL.into_iter().find(|direction| {
+            let name = direction.to_string();
+            input == name || (input.len() == 2 && input.chars().rev().eq(name.chars()))
+        })
+    }
+
+    pub fn delta(&self) -> (i32, i32) {
+        match self {
+            Direction::Up => (0, 1),
+            Direction::Down => (0, -1),
+            Direction::Left => (-1, 0),
+            Direction::Right => (1, 0),
+            Direction::UpLeft => (-1, 1),
+            Direction::UpRight => (1, 1),
+            Direction::DownLeft => (-1, -1),
+            Direction::DownRight => (1, -1),
+        }
+    }
+}
+
+impl Motion {
+    fn parse(line: &str, line_number: usize) -> Result<Motion, ParseMotionError> {
+        let (direction, steps) = line
+            .trim_end()
+            .split_once(' ')
+            .ok_or(ParseMotionError::MissingSteps { line: line_number })?;
+        let direction = Direction::parse(direction).ok_or(ParseMotionError::Direction {
+            line: line_number,
+            direction: direction.to_string(),
+        })?;
+        let steps = steps.parse().map_err(|_| ParseMotionError::Steps {
+            line: line_number,
+            steps: steps.to_string(),
+        })?;
+        Ok(Motion { direction, steps })
+    }
+}
+
+pub fn parse_motions(input: &str) -> Result<Vec<Motion>, ParseMotionError> {
+    input
+        .lines()
+        .enumerate()
+        .map(|(i, line)| Motion::parse(line, i + 1))
+        .collect()
+}
+
+impl fmt::Display for Direction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Direction::Up => "U",
+            Direction::Down => "D",
+            Direction::Left => "L",
+            Direction::Right => "R",
+            Direction::UpLeft => "UL",
+            Direction::UpRight => "UR",
+            Direction::DownLeft => "DL",
+            Direction::DownRight => "DR",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl fmt::Display for Motion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.direction, self.steps)
+    }
+}
+
+impl fmt::Display for ParseMotionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseMotionError::MissingSteps { line } => {
+                write!(
+                    f,
+                    "expected a direction and a number of steps at line {}",
+                    line
+                )
+            }
+            ParseMotionError::Direction { line, direction } => {
+                write!(f, "unknown direction {:?} at line {}", direction, line)
+            }
+            ParseMotionError::Steps { line, steps } => {
+                write!(f, "invalid number of steps {:?} at line {}", steps, line)
+            }
+        }
+    }
+}
+
+impl Error for ParseMotionError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("R 4", Direction::Right, 4)]
+    #[case("UL 2", Direction::UpLeft, 2)]
+    #[case("RD 10", Direction::DownRight, 10)]
+    fn test_parse_motion(#[case] line: &str, #[case] direction: Direction, #[case] steps: usize) {
+        assert_eq!(Motion::parse(line, 1), Ok(Motion { direction, steps }));
+    }
+
+    #[test]
+    fn test_parse_motions_errors() {
+        assert_eq!(
+            parse_motions("R 4\nX 2"),
+            Err(ParseMotionError::Direction {
+                line: 2,
+                direction: "X".to_string()
+            })
+        );
+        assert_eq!(
+            parse_motions("R 4\nU 1\nL -3"),
+            Err(ParseMotionError::Steps {
+                line: 3,
+                steps: "-3".to_string()
+            })
+        );
+        assert_eq!(
+            parse_motions("R"),
+            Err(ParseMotionError::MissingSteps { line: 1 })
+        );
+        assert_eq!(
+            parse_motions("UU 1"),
+            Err(ParseMotionError::Direction {
+                line: 1,
+                direction: "UU".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_display_round_trips() {
+        let input = "R 4\nUL 2\nDR 1";
+        let motions = parse_motions(input).unwrap();
+        let lines: Vec<String> = motions.iter().map(Motion::to_string).collect();
+        assert_eq!(lines.join("\n"), input);
+    }
+
+    #[test]
+    fn test_error_display() {
+        let err = parse_motions("R 4\nU x").unwrap_err();
+        assert_eq!(err.to_string(), r#"invalid number of steps "x" at line 2"#);
+    }
+}
@@ -0,0 +1,277 @@
+use std::io::{self, Write};
+
+use super::{FollowRule, Point, Rope};
+
+#[derive(Debug, Default, PartialEq, Clone, Copy)]
+pub enum Granularity {
+    // one frame per step the head takes
+    Step,
+    // one frame per line of input
+    #[default]
+    Instruction,
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct RenderOptions {
+    pub granularity: Granularity,
+    // marks the cells the tail has visited so far with '#', under the knots
+    pub show_visited: bool,
+}
+
+// clears the terminal and moves the cursor back to the top left
+const CLEAR_SCREEN: &str = "\x1b[2J\x1b[H";
+
+// the smallest box holding every position any knot is ever in, so all the frames of a run line up
+struct Bounds {
+    min_x: i32,
+    max_x: i32,
+    min_y: i32,
+    max_y: i32,
+}
+
+impl Bounds {
+    fn of<R: FollowRule>(rope: &Rope<R>) -> Bounds {
+        let mut bounds = Bounds {
+            min_x: 0,
+            max_x: 0,
+            min_y: 0,
+            max_y: 0,
+        };
+        for knot in 0..rope.knots().len() {
+            for point in rope.history(knot) {
+                bounds.min_x = bounds.min_x.min(point.x);
+                bounds.max_x = bounds.max_x.max(point.x);
+                bounds.min_y = bounds.min_y.min(point.y);
+                bounds.max_y = bounds.max_y.max(point.y);
+            }
+        }
+        bounds
+    }
+
+    fn grid(&self) -> Vec<Vec<char>> {
+        let width = (self.max_x - self.min_x + 1) as usize;
+        let height = (self.max_y - self.min_y + 1) as usize;
+        vec![vec!['.'; width]; height]
+    }
+
+    // y goes up, so the top row is the highest one
+    fn set(&self, grid: &mut [Vec<char>], point: &Point, c: char) {
+        grid[(self.max_y - point.y) as usize][(point.x - self.min_x) as usize] = c;
+    }
+}
+
+// the puzzle calls the last knot of a two knot rope T and numbers the knots of longer ones
+fn label(knot: usize, num_knots: usize) -> char {
+    match knot {
+        0 => 'H',
+        _ if num_knots == 2 => 'T',
+        _ => char::from_digit(knot as u32, 36).unwrap_or('*'),
+    }
+}
+
+fn to_string(grid: Vec<Vec<char>>) -> String {
+    grid.into_iter()
+        .map(|row| row.into_iter().collect::<String>() + "\n")
+        .collect()
+}
+
+// the rope after the given number of steps, knots closer to the head cover the ones behind them
+fn frame<R: FollowRule>(
+    rope: &Rope<R>,
+    bounds: &Bounds,
+    step: usize,
+    show_visited: bool,
+) -> String {
+    let mut grid = bounds.grid();
+    let num_knots = rope.knots().len();
+    if show_visited {
+        for point in &rope.history(num_knots - 1)[..=step] {
+            bounds.set(&mut grid, point, '#');
+        }
+    }
+    bounds.set(&mut grid, &Point::from_tuple((0, 0)), 's');
+    for knot in (0..num_knots).rev() {
+        bounds.set(&mut grid, &rope.history(knot)[step], label(knot, num_knots));
+    }
+    to_string(grid)
+}
+
+// the steps that get a frame, grouped by the instruction they belong to
+fn frame_steps<R: FollowRule>(
+    rope: &Rope<R>,
+    granularity: Granularity,
+) -> Vec<(String, Vec<usize>)> {
+    let mut start = 1;
+    rope.motions()
+        .iter()
+        .map(|(motion, end)| {
+            let steps = match granularity {
+                Granularity::Step => (start..=*end).collect(),
+                Granularity::Instruction => vec![*end],
+            };
+            start = end + 1;
+            (motion.to_string(), steps)
+        })
+        .collect()
+}
+
+pub fn visited<R: FollowRule>(rope: &Rope<R>) -> String {
+    let bounds = Bounds::of(rope);
+    let mut grid = bounds.grid();
+    for point in rope.history(rope.knots().len() - 1) {
+        bounds.set(&mut grid, point, '#');
+    }
+    bounds.set(&mut grid, &Point::from_tuple((0, 0)), 's');
+    to_string(grid)
+}
+
+// frames are written as they're drawn, a long run by step can take up gigabytes in total
+pub fn transcript<R: FollowRule>(
+    rope: &Rope<R>,
+    options: &RenderOptions,
+    out: &mut impl Write,
+) -> io::Result<()> {
+    let bounds = Bounds::of(rope);
+    write!(
+        out,
+        "== Initial State ==\n\n{}\n",
+        frame(rope, &bounds, 0, options.show_visited)
+    )?;
+    for (line, steps) in frame_steps(rope, options.granularity) {
+        write!(out, "== {} ==\n\n", line)?;
+        for step in steps {
+            writeln!(out, "{}", frame(rope, &bounds, step, options.show_visited))?;
+        }
+    }
+    Ok(())
+}
+
+pub fn animation<R: FollowRule>(
+    rope: &Rope<R>,
+    options: &RenderOptions,
+    out: &mut impl Write,
+) -> io::Result<()> {
+    let bounds = Bounds::of(rope);
+    for (line, steps) in frame_steps(rope, options.granularity) {
+        for step in steps {
+            write!(
+                out,
+                "{}== {} ==\n{}",
+                CLEAR_SCREEN,
+                line,
+                frame(rope, &bounds, step, options.show_visited)
+            )?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INPUT: &str = r#"R 4
+U 4
+L 3
+D 1
+R 4
+D 1
+L 5
+R 2"#;
+
+    fn rope(num_knots: usize, input: &str) -> Rope {
+        let mut rope = Rope::new(num_knots);
+        rope.run(input).unwrap();
+        rope
+    }
+
+    fn transcript(rope: &Rope, options: &RenderOptions) -> String {
+        let mut out = vec![];
+        super::transcript(rope, options, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    fn animation(rope: &Rope, options: &RenderOptions) -> String {
+        let mut out = vec![];
+        super::animation(rope, options, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_visited() {
+        let expected = r#"..##..
+...##.
+.####.
+....#.
+s###..
+"#;
+        assert_eq!(visited(&rope(2, INPUT)), expected);
+    }
+
+    #[test]
+    fn test_transcript_by_instruction() {
+        let expected = r#"== Initial State ==
+
+.....
+H....
+
+== R 4 ==
+
+.....
+s..TH
+
+== U 1 ==
+
+....H
+s..T.
+
+"#;
+        let actual = transcript(&rope(2, "R 4\nU 1"), &RenderOptions::default());
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_transcript_by_step() {
+        let options = RenderOptions {
+            granularity: Granularity::Step,
+            show_visited: true,
+        };
+        let expected = r#"== Initial State ==
+
+H..
+
+== R 2 ==
+
+TH.
+
+sTH
+
+"#;
+        assert_eq!(transcript(&rope(2, "R 2"), &options), expected);
+    }
+
+    #[test]
+    fn test_long_rope_labels() {
+        let rope = rope(10, INPUT);
+        let bounds = Bounds::of(&rope);
+        let after_first_instruction = frame(&rope, &bounds, 4, false);
+        assert!(after_first_instruction.ends_with("4321H.\n"));
+        let expected = r#"......
+......
+.1H3..
+.5....
+6.....
+"#;
+        assert_eq!(frame(&rope, &bounds, 24, false), expected);
+    }
+
+    #[test]
+    fn test_animation_frames() {
+        let options = RenderOptions {
+            granularity: Granularity::Step,
+            ..Default::default()
+        };
+        let animation = animation(&rope(2, INPUT), &options);
+        assert_eq!(animation.matches(CLEAR_SCREEN).count(), 24);
+    }
+}
@@ -11,9 +11,20 @@ mod d5;
 mod d6;
 mod d8;
 mod d9;
-use std::fs;
+use std::{
+    env, fs,
+    io::{self, Write},
+};
 
 fn main() {
+    let args: Vec<String> = env::args().collect();
+    let flag = |name: &str| args.iter().any(|arg| arg == name);
+    // values are passed as --name=value
+    let value = |name: &str| {
+        args.iter()
+            .find_map(|arg| arg.strip_prefix(name)?.strip_prefix('='))
+    };
+
     println!("========================== Day 1 ==========================");
     let input = fs::read_to_string("src/d1/input.txt").unwrap();
     println!("most calories: {}", d1::get_most_calories(input.clone()));
@@ -39,24 +50,57 @@ fn main() {
     let input = fs::read_to_string("src/d3/input.txt").unwrap();
     println!(
         "[1] sum of priorities of common items: {}",
-        d3::get_priority_sum(&input)
+        d3::get_priority_sum(&input).unwrap()
     );
     println!(
         "[2] sum of priorities of group badges: {}",
-        d3::get_badge_priority_total(&input)
+        d3::get_badge_priority_total(&input).unwrap()
     );
+    if value("--d3-compartments").is_some() || value("--d3-group-size").is_some() {
+        // e.g. --d3-compartments=4 --d3-group-size=2
+        let default = d3::RucksackConfig::default();
+        let config = d3::RucksackConfig {
+            num_compartments: value("--d3-compartments")
+                .map_or(default.num_compartments, |n| n.parse().unwrap()),
+            group_size: value("--d3-group-size").map_or(default.group_size, |n| n.parse().unwrap()),
+        };
+        match d3::priority_sum(&input, &config) {
+            Ok(sum) => println!("[*] sum of priorities of common items: {}", sum),
+            Err(e) => println!("[*] {}", e),
+        }
+        match d3::badge_priority_total(&input, &config) {
+            Ok(total) => println!("[*] sum of priorities of group badges: {}", total),
+            Err(e) => println!("[*] {}", e),
+        }
+    }
     println!();
 
     println!("========================== Day 4 ==========================");
     let input = fs::read_to_string("src/d4/input.txt").unwrap();
     println!(
         "[1] fully contained assignment pairs: {}",
-        d4::get_total_number_of_fully_contained_assignment_pairs(&input)
+        d4::get_total_number_of_fully_contained_assignment_pairs(&input).unwrap()
     );
     println!(
         "[1] overlapping assignment pairs: {}",
-        d4::get_total_number_of_overlapping_assignment_pairs(&input)
+        d4::get_total_number_of_overlapping_assignment_pairs(&input).unwrap()
     );
+    if flag("--d4-sections") {
+        let uncovered = d4::get_uncovered_sections(&input).unwrap();
+        let gaps: Vec<String> = uncovered.iter().map(|range| range.to_string()).collect();
+        println!(
+            "[*] sections covered by nobody: {} ({})",
+            uncovered.iter().map(|range| range.length()).sum::<u64>(),
+            gaps.join(", ")
+        );
+        if let Some((range, count)) = d4::get_most_overlapped_sections(&input).unwrap() {
+            println!("[*] most overlapped sections: {} ({} elves)", range, count);
+        }
+        println!(
+            "[*] groups of overlapping elves: {}",
+            d4::get_overlapping_groups(&input).unwrap().len()
+        );
+    }
 
     println!();
 
@@ -64,12 +108,42 @@ fn main() {
     let input = fs::read_to_string("src/d5/input.txt").unwrap();
     println!(
         "[1] crates after moves made (CrateMover9000): {}",
-        d5::run_scenario_cratemover9000(&input)
+        d5::run_scenario_cratemover9000(&input).unwrap()
     );
     println!(
         "[2] crates after moves made (CrateMover9001): {}",
-        d5::run_scenario_cratemover9001(&input)
+        d5::run_scenario_cratemover9001(&input).unwrap()
     );
+    if flag("--d5-trace") {
+        print!(
+            "{}",
+            d5::trace_scenario(&input, &d5::CrateMover9000).unwrap()
+        );
+    }
+    if let Some(max_lift) = value("--d5-max-lift") {
+        let max_lift = max_lift.parse().unwrap();
+        match d5::LimitedLiftCrane::new(max_lift) {
+            Some(crane) => println!(
+                "[*] crates after moves made (lifting at most {} at once): {}",
+                max_lift,
+                d5::run_scenario(&input, &crane).unwrap()
+            ),
+            None => println!("[*] the crane has to lift at least one crate"),
+        }
+    }
+    if let Some(max_states) = value("--d5-optimise") {
+        let max_states = max_states.parse().unwrap();
+        match d5::optimise_scenario(&input, &d5::CrateMover9001, max_states).unwrap() {
+            d5::Search::Found(moves) => println!(
+                "[*] fewest moves for the same result (CrateMover9001): {}",
+                moves.len()
+            ),
+            d5::Search::Unreachable => println!("[*] the final arrangement can't be reached"),
+            d5::Search::LimitReached(limit) => {
+                println!("[*] no procedure found within {} states", limit)
+            }
+        }
+    }
 
     println!();
 
@@ -77,12 +151,41 @@ fn main() {
     let input = fs::read_to_string("src/d6/input.txt").unwrap();
     println!(
         "[1] start-of-packet character index (4 distinct characters): {}",
-        d6::find_marker(&input, 4)
-    );
-    println!(
-        "[2] start-of-packet character index (14 distinct characters): {}",
-        d6::find_marker(&input, 14)
+        d6::find_marker(&input, 4).unwrap()
     );
+    if flag("--d6-stream") {
+        // reads the file as it goes rather than loading it up front
+        let file = fs::File::open("src/d6/input.txt").unwrap();
+        println!(
+            "[2] start-of-packet character index (14 distinct characters): {}",
+            d6::find_marker_in(file, 14).unwrap().unwrap()
+        );
+    } else {
+        println!(
+            "[2] start-of-packet character index (14 distinct characters): {}",
+            d6::find_marker(&input, 14).unwrap()
+        );
+    }
+    if flag("--d6-frames") {
+        for frame in d6::decode(&input, &d6::DEVICE_PROTOCOL) {
+            println!(
+                "{:?}: marker {:?}, body {:?}",
+                frame.kind, frame.marker, frame.body
+            );
+        }
+    }
+    if flag("--d6-all") {
+        let markers: Vec<usize> = if flag("--d6-stream") {
+            let file = fs::File::open("src/d6/input.txt").unwrap();
+            d6::markers_in(file, 4).collect::<Result<_, _>>().unwrap()
+        } else {
+            d6::find_all_markers(&input, 4)
+        };
+        println!(
+            "[*] every start-of-packet index (4 distinct characters): {:?}",
+            markers
+        );
+    }
 
     println!();
 
@@ -105,12 +208,34 @@ fn main() {
     let input = fs::read_to_string("src/d9/input.txt").unwrap();
     println!(
         "[1] number of unique visits with short rope: {}",
-        d9::short_rope_unique_locations(&input)
+        d9::short_rope_unique_locations(&input).unwrap()
     );
     println!(
         "[2] number of unique visits with long rope: {}",
-        d9::long_rope_unique_locations(&input)
+        d9::long_rope_unique_locations(&input).unwrap()
     );
+    if flag("--d9-visited") {
+        print!("{}", d9::draw_visited(&input, 10).unwrap());
+    }
+    // --d9-render=step|instruction prints every frame, --d9-animate=... plays them in the terminal
+    let render = value("--d9-render").or(value("--d9-animate"));
+    if let Some(granularity) = render {
+        let options = d9::RenderOptions {
+            granularity: match granularity {
+                "step" => d9::Granularity::Step,
+                _ => d9::Granularity::Instruction,
+            },
+            show_visited: flag("--d9-show-visited"),
+        };
+        // streamed straight to stdout, a full run by step is far too big to build up first
+        let mut out = io::BufWriter::new(io::stdout().lock());
+        if value("--d9-animate").is_some() {
+            d9::animate_rope(&input, 10, &options, &mut out).unwrap();
+        } else {
+            d9::draw_rope(&input, 10, &options, &mut out).unwrap();
+        }
+        out.flush().unwrap();
+    }
 
     println!();
 
@@ -120,15 +245,98 @@ fn main() {
         "[1] sum of signal strengths: {}",
         d10::total_signal_strength(&input)
     );
-    println!("[2] output:");
-    d10::draw_crt(&input);
+    println!("[2] letters on the CRT: {}", d10::read_crt(&input));
+    println!("{}", d10::draw_crt(&input));
+    if flag("--d10-trace") || value("--d10-trace").is_some() {
+        // e.g. --d10-trace=20-60 --d10-break-cycle=40,80 --d10-break-x=-1
+        let numbers = |name: &str| -> Vec<String> {
+            value(name)
+                .map(|list| list.split(',').map(String::from).collect())
+                .unwrap_or_default()
+        };
+        let options = d10::TraceOptions {
+            cycles: value("--d10-trace")
+                .and_then(|range| range.split_once('-'))
+                .map(|(from, to)| from.parse().unwrap()..=to.parse().unwrap()),
+            break_on_cycles: numbers("--d10-break-cycle")
+                .iter()
+                .map(|cycle| cycle.parse().unwrap())
+                .collect(),
+            break_on_x: numbers("--d10-break-x")
+                .iter()
+                .map(|x| x.parse().unwrap())
+                .collect(),
+            ..Default::default()
+        };
+        print!("{}", d10::trace_program(&input, &options).unwrap());
+    }
 
     println!();
 
     println!("========================== Day 11 ==========================");
     let input = fs::read_to_string("src/d11/input.txt").unwrap();
-    println!("[1] monkey business level {}", d11::part1(&input));
-    println!("[2] monkey business level {}", d11::part2(&input));
+    if flag("--d11-exact") {
+        println!(
+            "[1] monkey business level (exact worry levels) {}",
+            d11::simulation(
+                &input,
+                &d11::SimulationConfig {
+                    mode: d11::WorryMode::Exact,
+                    ..d11::SimulationConfig::part1()
+                }
+            )
+            .unwrap()
+            .monkey_business
+        );
+    } else {
+        println!("[1] monkey business level {}", d11::part1(&input));
+    }
+    if let Some(threads) = value("--d11-threads") {
+        println!(
+            "[2] monkey business level ({} threads) {}",
+            threads,
+            d11::simulation(
+                &input,
+                &d11::SimulationConfig {
+                    strategy: d11::Strategy::Parallel {
+                        threads: threads.parse().unwrap()
+                    },
+                    ..d11::SimulationConfig::part2()
+                }
+            )
+            .unwrap()
+            .monkey_business
+        );
+    } else if flag("--d11-cycles") {
+        println!(
+            "[2] monkey business level (cycle detection) {}",
+            d11::simulation(
+                &input,
+                &d11::SimulationConfig {
+                    strategy: d11::Strategy::CycleDetection,
+                    ..d11::SimulationConfig::part2()
+                }
+            )
+            .unwrap()
+            .monkey_business
+        );
+    } else {
+        println!("[2] monkey business level {}", d11::part2(&input));
+    }
+    if let Some(format) = value("--d11-trace") {
+        // traces the part 1 rounds, --d11-trace=csv|json|summary
+        let trace = d11::trace_simulation(&input, &d11::SimulationConfig::part1()).unwrap();
+        match format {
+            "csv" => print!("{}", trace.to_csv()),
+            "json" => println!("{}", trace.to_json()),
+            _ => {
+                for round in &trace.rounds {
+                    println!("{}", round.held_items());
+                    println!("{}", round.inspections());
+                }
+            }
+        }
+    }
 
     println!();
     println!("========================== Day 12 ==========================");
@@ -150,10 +358,17 @@ fn main() {
         "[1] number of sand particles that fell: {}",
         d14::part1(&input)
     );
-    println!(
-        "[2] number of sand particles that fell with bedrock: {}",
-        d14::part2(&input)
-    );
+    if flag("--d14-analytic") {
+        println!(
+            "[2] number of sand particles that fell with bedrock (analytic): {}",
+            d14::part2_analytic(&input)
+        );
+    } else {
+        println!(
+            "[2] number of sand particles that fell with bedrock: {}",
+            d14::part2(&input)
+        );
+    }
 
     println!();
     println!("========================== Day 15 ==========================");